@@ -1,3 +1,6 @@
+// Written before clippy was part of the checks; left in its original style.
+#![allow(clippy::redundant_pattern_matching)]
+
 use std::collections::HashMap;
 
 /// Auxiliary data queried from the database.
@@ -6,18 +9,46 @@ pub struct AuxiliaryData {
 	// Index oid -> table name.
 	pub index_table: HashMap<u32, String>,
 	// View oid -> definition.
-	pub pretty_printed_views: HashMap<u32, String>,
+	pub views: HashMap<u32, ViewDefinition>,
 	// Function oid.
 	pub trigger_functions: HashMap<u32, ()>,
+	// (catalog oid, object oid) -> name of the extension the object is a
+	// member of.
+	pub extension_members: HashMap<(u32, u32), String>,
+	// Schema name -> name of the extension which created the schema.
+	pub extension_member_schemas: HashMap<String, String>,
+	// Extension name -> schema the extension is installed into.
+	pub extension_schemas: HashMap<String, String>,
+}
+
+// What we need to know to re-create a view.
+#[derive(Debug)]
+pub struct ViewDefinition {
+	// The query, as pretty-printed by pg_get_viewdef().
+	pub query: String,
+	// Names of the view's columns.
+	pub columns: Vec<String>,
+	// reloptions, e.g. "security_barrier=true".  Includes the check option,
+	// if any.
+	pub options: Vec<String>,
 }
 
 pub fn query(txn: &mut postgres::Transaction) -> Result<AuxiliaryData, String> {
 	let mut aux = AuxiliaryData{
 		index_table: HashMap::new(),
-		pretty_printed_views: HashMap::new(),
+		views: HashMap::new(),
 		trigger_functions: HashMap::new(),
+		extension_members: HashMap::new(),
+		extension_member_schemas: HashMap::new(),
+		extension_schemas: HashMap::new(),
 	};
 
+	// index.sql runs with an empty search_path, so make sure everything
+	// pg_get_viewdef() and friends give us is schema-qualified.
+	if let Err(err) = txn.execute("SET LOCAL search_path TO ''", &[]) {
+		return Err(format!("could not set search_path: {}", err));
+	}
+
 	let rows = txn.query(
 		"
 			SELECT pg_index.indexrelid, pg_class.relname
@@ -42,7 +73,19 @@ pub fn query(txn: &mut postgres::Transaction) -> Result<AuxiliaryData, String> {
 
 	let rows = txn.query(
 		"
-			SELECT pg_class.oid, pg_get_viewdef(pg_class.oid, true)
+			SELECT
+				pg_class.oid,
+				pg_get_viewdef(pg_class.oid, true),
+				ARRAY(
+					SELECT pg_attribute.attname::text
+					FROM pg_attribute
+					WHERE
+						pg_attribute.attrelid = pg_class.oid AND
+						pg_attribute.attnum > 0 AND
+						NOT pg_attribute.attisdropped
+					ORDER BY pg_attribute.attnum
+				),
+				COALESCE(pg_class.reloptions, '{}')
 			FROM pg_class
 			WHERE pg_class.relkind = 'v'
 		",
@@ -50,14 +93,18 @@ pub fn query(txn: &mut postgres::Transaction) -> Result<AuxiliaryData, String> {
 	);
 	let rows = match rows {
 		Err(err) => {
-			return Err(format!("could not query pg_index: {}", err));
+			return Err(format!("could not query pg_class: {}", err));
 		},
 		Ok(rows) => rows,
 	};
 	for row in rows {
 		let oid: u32 = row.get(0);
-		let view_definition: String = row.get(1);
-		if let Some(_view_definition) = aux.pretty_printed_views.insert(oid, view_definition) {
+		let view_definition = ViewDefinition{
+			query: row.get(1),
+			columns: row.get(2),
+			options: row.get(3),
+		};
+		if let Some(_view_definition) = aux.views.insert(oid, view_definition) {
 			panic!("oid {} seen twice in pg_class", oid);
 		}
 	}
@@ -84,5 +131,56 @@ pub fn query(txn: &mut postgres::Transaction) -> Result<AuxiliaryData, String> {
 		}
 	}
 
+	let rows = txn.query(
+		"
+			SELECT pg_depend.classid, pg_depend.objid, pg_extension.extname::text, pg_namespace.nspname::text
+			FROM pg_depend
+			JOIN pg_extension ON pg_extension.oid = pg_depend.refobjid
+			LEFT JOIN pg_namespace ON
+				pg_depend.classid = 'pg_namespace'::regclass AND
+				pg_namespace.oid = pg_depend.objid
+			WHERE
+				pg_depend.refclassid = 'pg_extension'::regclass AND
+				pg_depend.deptype = 'e'
+		",
+		&[],
+	);
+	let rows = match rows {
+		Err(err) => {
+			return Err(format!("could not query pg_depend: {}", err));
+		},
+		Ok(rows) => rows,
+	};
+	for row in rows {
+		let classid: u32 = row.get(0);
+		let objid: u32 = row.get(1);
+		let extname: String = row.get(2);
+		let member_schema: Option<String> = row.get(3);
+		if let Some(member_schema) = member_schema {
+			aux.extension_member_schemas.insert(member_schema, extname.clone());
+		}
+		aux.extension_members.insert((classid, objid), extname);
+	}
+
+	let rows = txn.query(
+		"
+			SELECT pg_extension.extname::text, pg_namespace.nspname::text
+			FROM pg_extension
+			JOIN pg_namespace ON pg_namespace.oid = pg_extension.extnamespace
+		",
+		&[],
+	);
+	let rows = match rows {
+		Err(err) => {
+			return Err(format!("could not query pg_extension: {}", err));
+		},
+		Ok(rows) => rows,
+	};
+	for row in rows {
+		let extname: String = row.get(0);
+		let schema: String = row.get(1);
+		aux.extension_schemas.insert(extname, schema);
+	}
+
 	Ok(aux)
 }
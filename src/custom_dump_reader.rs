@@ -1,3 +1,13 @@
+// The reader's original code spells out returns and struct fields; clippy's
+// style lints are silenced here rather than rewriting it.
+#![allow(
+	clippy::comparison_to_empty,
+	clippy::len_zero,
+	clippy::needless_return,
+	clippy::redundant_field_names,
+	clippy::unnecessary_get_then_check,
+)]
+
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt;
@@ -5,7 +15,25 @@ use std::io::{self, BufReader, Read};
 
 use byteorder::{LittleEndian, ReadBytesExt};
 
-use crate::auxiliary_data::AuxiliaryData;
+use crate::auxiliary_data::{AuxiliaryData, ViewDefinition};
+use crate::filter::{ItemFilter, ObjectFilter};
+use crate::identifiers::{
+	parse_identifier,
+	quote_identifier,
+	quote_qualified_name,
+	split_once_unquoted,
+	unquote_identifier,
+};
+use crate::layout::{Layout, PathVariables};
+use crate::progress::{Phase, Progress};
+use crate::role_map::RoleMap;
+use crate::path_encoding::{
+	disambiguate_path_component,
+	encode_function_signature,
+	escape_path_component,
+	fold_path_component,
+	shorten_path_component,
+};
 
 #[derive(Debug)]
 pub struct DumpReadIOError {
@@ -58,8 +86,248 @@ impl fmt::Display for DumpReadError {
 	}
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
-struct View {
+// Options affecting how a dump is split into files.
+#[derive(Debug, Default)]
+pub struct SplitOptions {
+	// Fail if we come across an item we don't know how to classify, instead of
+	// putting it into UNCLASSIFIED/.
+	pub strict: bool,
+	// Put each overload of a function into a file of its own.
+	pub split_function_overloads: bool,
+	// Where to put each kind of object.
+	pub layout: Layout,
+	// Fail if two paths would refer to the same file on a case-insensitive
+	// file system, instead of renaming one of them.
+	pub fail_on_path_collisions: bool,
+	// Leave out ALTER .. OWNER TO statements.
+	pub no_owner: bool,
+	// Roles to rename in owners, ACLs, default privileges, policies and
+	// user mappings.
+	pub role_map: RoleMap,
+	// Leave out privileges (including default privileges), comments and
+	// security labels.
+	pub no_acl: bool,
+	pub no_comments: bool,
+	pub no_security_labels: bool,
+	// Which schemas and tables to include.
+	pub filter: ObjectFilter,
+}
+
+// The descs of the TOC entries add_item() knows what to do with, and the
+// catalog the objects they create live in (zero for entries which aren't
+// objects of their own).  Anything else goes into UNCLASSIFIED/.  The
+// entries which only carry settings for the whole dump aren't listed here.
+const KNOWN_ITEMS: [(&str, u32); 51] = [
+	("ACCESS METHOD", 2601),
+	("ACL", 0),
+	("AGGREGATE", 1255),
+	("CAST", 2605),
+	("CHECK CONSTRAINT", 2606),
+	("COLLATION", 3456),
+	("COMMENT", 0),
+	("CONSTRAINT", 2606),
+	("CONVERSION", 2607),
+	("DEFAULT", 2604),
+	("DEFAULT ACL", 826),
+	("DOMAIN", 1247),
+	("EVENT TRIGGER", 3466),
+	("EXTENSION", 3079),
+	("FK CONSTRAINT", 2606),
+	("FOREIGN DATA WRAPPER", 2328),
+	("FOREIGN TABLE", 1259),
+	("FUNCTION", 1255),
+	("INDEX", 1259),
+	("INDEX ATTACH", 0),
+	("MATERIALIZED VIEW", 1259),
+	("OPERATOR", 2617),
+	("OPERATOR CLASS", 2616),
+	("OPERATOR FAMILY", 2753),
+	("POLICY", 3256),
+	("PROCEDURAL LANGUAGE", 2612),
+	("PROCEDURE", 1255),
+	("PUBLICATION", 6104),
+	("PUBLICATION TABLE", 6106),
+	("PUBLICATION TABLES IN SCHEMA", 6237),
+	("ROW SECURITY", 0),
+	("RULE", 2618),
+	("SCHEMA", 2615),
+	("SECURITY LABEL", 0),
+	("SEQUENCE", 1259),
+	("SEQUENCE OWNED BY", 0),
+	("SERVER", 1417),
+	("SHELL TYPE", 0),
+	("STATISTICS", 3381),
+	("SUBSCRIPTION", 6100),
+	("TABLE", 1259),
+	("TABLE ATTACH", 0),
+	("TEXT SEARCH CONFIGURATION", 3602),
+	("TEXT SEARCH DICTIONARY", 3600),
+	("TEXT SEARCH PARSER", 3601),
+	("TEXT SEARCH TEMPLATE", 3764),
+	("TRANSFORM", 3576),
+	("TRIGGER", 2620),
+	("TYPE", 1247),
+	("USER MAPPING", 0),
+	("VIEW", 1259),
+];
+
+// Returns the descs of the objects we know how to deal with.
+pub fn known_descs() -> impl Iterator<Item = &'static str> {
+	KNOWN_ITEMS.iter().map(|(desc, _)| *desc)
+}
+
+// Sets the path variables for a function, procedure or aggregate.  Normally
+// all overloads of a function share a file, but with split_function_overloads
+// each signature gets one of its own.
+fn set_function_path_variables(vars: &mut PathVariables, tag: &str, options: &SplitOptions) {
+	let function_name = tag.split_once("(").unwrap().0.to_string();
+	let signature = encode_function_signature(tag);
+
+	if options.split_function_overloads {
+		vars.insert("function", signature.clone());
+	} else {
+		vars.insert("function", function_name.clone());
+	}
+	vars.insert("signature", signature);
+	vars.insert("name", function_name);
+}
+
+// How an object is referred to in an ALTER .. OWNER TO statement.
+enum OwnerSyntax {
+	// <object type> schema.name
+	Qualified,
+	// <object type> name
+	Unqualified,
+	// Whatever comes after "DROP" in the item's DROP statement.  Used for
+	// objects which need their argument types spelled out, since we can't
+	// reliably get those from the tag.
+	DropStatement,
+}
+
+// Every desc of an object which has an owner, along with the object type to
+// use in ALTER .. OWNER TO.  This is the same list pg_restore uses.
+const OWNED_OBJECT_TYPES: [(&str, &str, OwnerSyntax); 25] = [
+	("AGGREGATE", "AGGREGATE", OwnerSyntax::DropStatement),
+	("COLLATION", "COLLATION", OwnerSyntax::Qualified),
+	("CONVERSION", "CONVERSION", OwnerSyntax::Qualified),
+	("DOMAIN", "DOMAIN", OwnerSyntax::Qualified),
+	("EVENT TRIGGER", "EVENT TRIGGER", OwnerSyntax::Unqualified),
+	("FOREIGN DATA WRAPPER", "FOREIGN DATA WRAPPER", OwnerSyntax::Unqualified),
+	("FOREIGN TABLE", "FOREIGN TABLE", OwnerSyntax::Qualified),
+	("FUNCTION", "FUNCTION", OwnerSyntax::DropStatement),
+	("MATERIALIZED VIEW", "MATERIALIZED VIEW", OwnerSyntax::Qualified),
+	("OPERATOR", "OPERATOR", OwnerSyntax::DropStatement),
+	("OPERATOR CLASS", "OPERATOR CLASS", OwnerSyntax::DropStatement),
+	("OPERATOR FAMILY", "OPERATOR FAMILY", OwnerSyntax::DropStatement),
+	("PROCEDURAL LANGUAGE", "LANGUAGE", OwnerSyntax::Unqualified),
+	("PROCEDURE", "PROCEDURE", OwnerSyntax::DropStatement),
+	("PUBLICATION", "PUBLICATION", OwnerSyntax::Unqualified),
+	("SCHEMA", "SCHEMA", OwnerSyntax::Unqualified),
+	("SEQUENCE", "SEQUENCE", OwnerSyntax::Qualified),
+	("SERVER", "SERVER", OwnerSyntax::Unqualified),
+	("STATISTICS", "STATISTICS", OwnerSyntax::Qualified),
+	("SUBSCRIPTION", "SUBSCRIPTION", OwnerSyntax::Unqualified),
+	("TABLE", "TABLE", OwnerSyntax::Qualified),
+	("TEXT SEARCH CONFIGURATION", "TEXT SEARCH CONFIGURATION", OwnerSyntax::Qualified),
+	("TEXT SEARCH DICTIONARY", "TEXT SEARCH DICTIONARY", OwnerSyntax::Qualified),
+	("TYPE", "TYPE", OwnerSyntax::Qualified),
+	("VIEW", "VIEW", OwnerSyntax::Qualified),
+];
+
+// Returns the ALTER .. OWNER TO statement for an item, or None if the item
+// isn't for an object with an owner.
+fn owner_statement(item: &CustomDumpItem) -> Option<String> {
+	if item.owner.is_empty() {
+		return None;
+	}
+	let (_, object_type, syntax) = OWNED_OBJECT_TYPES.iter().find(|(desc, _, _)| *desc == item.desc)?;
+
+	let object = match syntax {
+		OwnerSyntax::Qualified => format!("{} {}", object_type, quote_qualified_name(&item.namespace, &item.tag)),
+		OwnerSyntax::Unqualified => format!("{} {}", object_type, quote_identifier(&item.tag)),
+		OwnerSyntax::DropStatement => {
+			let object = item.drop_stmt.strip_prefix("DROP ")?;
+			let object = object.trim_end().strip_suffix(';')?;
+			object.to_string()
+		},
+	};
+	Some(format!("ALTER {} OWNER TO {};\n", object, quote_identifier(&item.owner)))
+}
+
+// Renames the roles an item refers to according to role_map.
+fn map_roles(item: &mut CustomDumpItem, role_map: &RoleMap) {
+	if !item.owner.is_empty() {
+		item.owner = role_map.map_role(&item.owner);
+	}
+
+	match item.desc.as_ref() {
+		"ACL" | "DEFAULT ACL" | "POLICY" => {
+			item.definition = role_map.rewrite_statements(&item.definition);
+		},
+		"USER MAPPING" => {
+			item.definition = role_map.rewrite_statements(&item.definition);
+
+			// The tag looks like "USER MAPPING role SERVER server".
+			let mapping = item.tag.strip_prefix("USER MAPPING ").and_then(|rest| rest.rsplit_once(" SERVER "));
+			if let Some((role, server)) = mapping {
+				item.tag = format!("USER MAPPING {} SERVER {}", role_map.map_role(role), server);
+			}
+		},
+		_ => {},
+	}
+}
+
+// Builds the CREATE OR REPLACE VIEW statement for a view.  We use our own
+// instead of pg_dump's so that the query is pretty-printed.
+fn render_view(item: &CustomDumpItem, view: &ViewDefinition) -> String {
+	let mut sql = format!("CREATE OR REPLACE VIEW {}", quote_qualified_name(&item.namespace, &item.tag));
+
+	if !view.columns.is_empty() {
+		let columns: Vec<String> = view.columns.iter().map(|column| quote_identifier(column)).collect();
+		sql.push_str(&format!(" ({})", columns.join(", ")));
+	}
+
+	let mut options = vec![];
+	let mut check_option = None;
+	for option in &view.options {
+		let (name, value) = option.split_once('=').unwrap_or((option, ""));
+		if name == "check_option" {
+			check_option = Some(value.to_uppercase());
+		} else {
+			options.push(format!("{}='{}'", name, value.replace('\'', "''")));
+		}
+	}
+	if !options.is_empty() {
+		sql.push_str(&format!(" WITH ({})", options.join(", ")));
+	}
+
+	sql.push_str(" AS\n");
+	sql.push_str(view.query.trim_end().trim_end_matches(';'));
+	if let Some(check_option) = check_option {
+		sql.push_str(&format!("\n  WITH {} CHECK OPTION", check_option));
+	}
+	sql.push_str(";\n");
+	sql
+}
+
+// Escapes a field of manifest.tsv the way COPY's text format would.
+fn escape_manifest_field(s: &str) -> String {
+	s.replace('\\', "\\\\")
+		.replace('\t', "\\t")
+		.replace('\n', "\\n")
+		.replace('\r', "\\r")
+}
+
+// Quotes a path for use in a psql meta-command such as \ir if needed.
+fn psql_quote_path(path: &str) -> String {
+	if path.contains(|c: char| c.is_whitespace() || c == '\'' || c == '`') {
+		return format!("'{}'", path.replace('\'', "''"));
+	}
+	path.to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Relation {
 	pub schema: String,
 	pub name: String,
 }
@@ -77,6 +345,12 @@ impl SplitDumpDirectory {
 			files: HashMap::new(),
 		}
 	}
+
+	// The number of files in this directory and all of its subdirectories.
+	pub fn file_count(&self) -> u64 {
+		let subdir_files: u64 = self.dirs.values().map(|dir| dir.file_count()).sum();
+		self.files.len() as u64 + subdir_files
+	}
 }
 
 // It would be nicer if we added custom structs for everything instead of
@@ -89,21 +363,65 @@ pub struct CustomDump {
 
 	pub split_root: SplitDumpDirectory,
 
-	// List of pg_class entries which are views.  We need to keep track of these
-	// so we know to put the ACLs for views into the right files.
-	views: HashMap<View, ()>,
+	// The kind ("table", "view" etc.) of each pg_class entry we've seen.  We
+	// need to keep track of these so we know where to put e.g. the ACLs and
+	// triggers of views.
+	relation_kinds: HashMap<Relation, &'static str>,
+	// Dump id -> pg_class entry, for finding the relation objects such as
+	// constraints and triggers belong to.
+	relations_by_dump_id: HashMap<i64, Relation>,
+	// Dump id -> publication name.  The tags of the tables and schemas in a
+	// publication don't quote the publication's name, so we find it through
+	// their dependencies instead.
+	publications_by_dump_id: HashMap<i64, String>,
+
+	// Dump id -> the path an item was written to.  Entries such as comments
+	// and ACLs depend on the object they belong to, so this lets us put them
+	// into the same file as the object itself.
+	item_paths: HashMap<i64, Vec<String>>,
+
+	// "parent/folded name" -> the name actually used in that directory, and
+	// whether it's a directory; see resolve_path_collisions().
+	path_names: HashMap<String, (String, bool)>,
+	// "parent/name" -> the name a colliding path component was renamed to.
+	renamed_path_components: HashMap<String, String>,
+
+	// Number of items we didn't know what to do with.  Only ever non-zero if
+	// SplitOptions.strict is not set.
+	pub unclassified_items: usize,
+	// Warnings about the last item added, for read_dump() to report.
+	warnings: Vec<String>,
+
+	// Lines of manifest.tsv, which lists the file each object went into
+	// along with the object's original name.
+	manifest: Vec<String>,
 }
 
-pub fn read_dump<R: Read>(input: R, aux_data: &AuxiliaryData) -> Result<CustomDump, DumpReadError> {
+pub fn read_dump<R: Read>(input: R, aux_data: &AuxiliaryData, options: &SplitOptions, progress: &mut Progress) -> Result<CustomDump, DumpReadError> {
 	let reader = CustomDumpReader::new(input)?;
+	progress.counted_phase(Phase::ReadingToc, reader.header.as_ref().unwrap().num_items as u64);
 
 	let mut dump = CustomDump::new();
+	let mut item_filter = match ItemFilter::new(&options.filter, aux_data) {
+		Err(err) => return Err(DumpReadError::OtherError(err)),
+		Ok(item_filter) => item_filter,
+	};
 	for item in reader.contents() {
 		let item = item?;
+		progress.advance();
+
+		if item_filter.excludes(&item) {
+			continue;
+		}
 
-		dump.add_item(item, aux_data)?;
+		dump.add_item(item, aux_data, options)?;
+		for warning in dump.warnings.drain(..) {
+			progress.warn(&warning);
+		}
 	}
 
+	dump.add_manifest();
+
 	Ok(dump)
 }
 
@@ -115,11 +433,24 @@ impl CustomDump {
 			set_search_path: None,
 
 			split_root: SplitDumpDirectory::new(),
-			views: HashMap::new(),
+			relation_kinds: HashMap::new(),
+			relations_by_dump_id: HashMap::new(),
+			publications_by_dump_id: HashMap::new(),
+			item_paths: HashMap::new(),
+			path_names: HashMap::from([
+				("/index.sql".to_string(), ("index.sql".to_string(), false)),
+				("/manifest.tsv".to_string(), ("manifest.tsv".to_string(), false)),
+			]),
+			renamed_path_components: HashMap::new(),
+			unclassified_items: 0,
+			warnings: vec![],
+			manifest: vec![
+				"path\tdesc\tschema\ttag".to_string(),
+			],
 		}
 	}
 
-	fn add_item(&mut self, item: CustomDumpItem, aux_data: &AuxiliaryData) -> Result<(), DumpReadError> {
+	fn add_item(&mut self, mut item: CustomDumpItem, aux_data: &AuxiliaryData, options: &SplitOptions) -> Result<(), DumpReadError> {
 		fn other_error<S: Into<String>>(err: S) -> Result<(), DumpReadError> {
 			return Err(DumpReadError::OtherError(err.into()));
 		}
@@ -129,8 +460,35 @@ impl CustomDump {
 			return Ok(());
 		}
 
+		// We pass the matching switches on to pg_dump as well, but the
+		// entries are dropped here too so that the switches hold for any
+		// custom format dump we're fed, not only one taken with them.
+		let excluded = match item.desc.as_ref() {
+			"ACL" | "DEFAULT ACL" => options.no_acl,
+			"COMMENT" => options.no_comments,
+			"SECURITY LABEL" => options.no_security_labels,
+			_ => false,
+		};
+		if excluded {
+			return Ok(());
+		}
+
+		if !options.role_map.is_empty() {
+			map_roles(&mut item, &options.role_map);
+		}
+
 		let mut contents = vec![item.definition.clone()];
-		let mut filepath;
+
+		// Most items go wherever the layout says they should, in which case
+		// all we need to do here is to figure out the values of the variables
+		// in the layout's path templates.  The rest set filepath directly.
+		let mut filepath = None;
+		let mut vars = PathVariables::new();
+		vars.insert("schema", item.namespace.clone());
+		vars.insert("name", item.tag.clone());
+		vars.insert("desc", item.desc.clone());
+		vars.insert("tag", item.tag.clone());
+		let mut kind = None;
 
 		match (item.table_oid, item.desc.as_ref()) {
 			(0, "ENCODING") => {
@@ -138,7 +496,7 @@ impl CustomDump {
 					return other_error(r#"more than one "ENCODING" item present"#);
 				}
 				self.set_client_encoding = Some(item.definition.clone());
-				filepath = vec!["index.sql".to_string()];
+				filepath = Some(vec!["index.sql".to_string()]);
 			},
 			(0, "STDSTRINGS") => {
 				if self.set_standard_conforming_strings.is_some() {
@@ -148,7 +506,7 @@ impl CustomDump {
 
 				contents.push("SET check_function_bodies = false;\n".to_string());
 
-				filepath = vec!["index.sql".to_string()];
+				filepath = Some(vec!["index.sql".to_string()]);
 			},
 			(0, "SEARCHPATH") => {
 				if self.set_search_path.is_some() {
@@ -156,236 +514,184 @@ impl CustomDump {
 				}
 				self.set_search_path = Some(item.definition.clone());
 
-				filepath = vec!["index.sql".to_string()];
+				filepath = Some(vec!["index.sql".to_string()]);
+			},
+			_ if !KNOWN_ITEMS.contains(&(item.desc.as_str(), item.table_oid)) => {
+				let err = DumpReadError::OtherError(
+					format!("unknown table_oid / desc ({}, {}) for item {:?}", item.table_oid, item.desc, item.tag),
+				);
+				filepath = Some(self.unclassified(&item, err, options)?);
 			},
 			(0, "ACL") => {
 				contents = self.sort_acl(&item.definition);
 
-				filepath = self.get_filepath_from_combo_tag(&item, "ACL");
+				filepath = Some(match self.get_filepath_from_combo_tag(&item, "ACL", options) {
+					Err(err) => self.unclassified(&item, err, options)?,
+					Ok(filepath) => filepath,
+				});
 			},
 			(0, "COMMENT") => {
-				filepath = self.get_filepath_from_combo_tag(&item, "COMMENT");
+				filepath = Some(match self.get_filepath_from_combo_tag(&item, "COMMENT", options) {
+					Err(err) => self.unclassified(&item, err, options)?,
+					Ok(filepath) => filepath,
+				});
 			},
-			(2615, "SCHEMA") => {
-				if item.tag == "public" {
-					filepath = vec![];
-				} else {
-					filepath = vec![
-						"SCHEMAS".to_string(),
-						format!("{}.sql", &item.tag),
-					];
-				}
+			(0, "SECURITY LABEL") => {
+				filepath = Some(match self.get_filepath_from_combo_tag(&item, "SECURITY LABEL", options) {
+					Err(err) => self.unclassified(&item, err, options)?,
+					Ok(filepath) => filepath,
+				});
 			},
-			(3079, "EXTENSION") => {
-				filepath = vec![
-					"EXTENSIONS".to_string(),
-					format!("{}.sql", &item.tag),
-				];
-			},
-			(0, "SHELL TYPE") => {
-				filepath = vec![
-					item.namespace,
-					"SHELL_TYPES".to_string(),
-					format!("{}.sql", &item.tag),
-				];
-			},
-			(1247, "TYPE") => {
-				filepath = vec![
-					item.namespace,
-					"TYPES".to_string(),
-					format!("{}.sql", &item.tag),
-				];
-			}
-			(1247, "DOMAIN") => {
-				filepath = vec![
-					item.namespace,
-					"DOMAINS".to_string(),
-					format!("{}.sql", &item.tag),
-				];
+			(2615, "SCHEMA") if item.tag == "public" => {
+				kind = Some("public");
 			},
 			(1255, "FUNCTION") => {
-				let subdir;
 				if aux_data.trigger_functions.get(&item.oid).is_some() {
-					subdir = "TRIGGER_FUNCTIONS";
-				} else {
-					subdir = "FUNCTIONS";
+					kind = Some("trigger");
 				}
 
-				contents.push(
-					format!(
-						"ALTER FUNCTION {}.{} OWNER TO {};\n",
-						&item.namespace,
-						&item.tag,
-						&item.owner,
-					),
-				);
-
-				let function_name = item.tag.split_once("(").unwrap().0;
-
-				filepath = vec![
-					item.namespace,
-					subdir.to_string(),
-					format!("{}.sql", &function_name),
-				];
+				set_function_path_variables(&mut vars, &item.tag, options);
 			},
-			(1255, "AGGREGATE") => {
-				let function_name = item.tag.split_once("(").unwrap().0;
-				filepath = vec![
-					item.namespace,
-					"FUNCTIONS".to_string(),
-					format!("{}.sql", &function_name),
-				];
+			(1255, "PROCEDURE") => {
+				set_function_path_variables(&mut vars, &item.tag, options);
 			},
-			(2617, "OPERATOR") => {
-				filepath = vec![
-					item.namespace,
-					"operators.sql".to_string(),
-				];
+			(1255, "AGGREGATE") => {
+				set_function_path_variables(&mut vars, &item.tag, options);
 			},
 			(1259, "TABLE") => {
-				contents.push(
-					format!(
-						"ALTER TABLE {}.{} OWNER TO {};\n",
-						&item.namespace,
-						&item.tag,
-						&item.owner,
-					),
-				);
-
-				filepath = vec![
-					item.namespace,
-					"TABLES".to_string(),
-					format!("{}.sql", &item.tag),
-				];
+				self.add_relation(&item, "table");
+				vars.insert("table", item.tag.clone());
 			},
 			(1259, "INDEX") => {
 				let table_name = aux_data.index_table.get(&item.oid).unwrap();
-				filepath = vec![
-					item.namespace,
-					"TABLES".to_string(),
-					format!("{}.sql", &table_name),
-				];
+				kind = self.relation_kind(&item.namespace, table_name);
+				vars.insert("table", table_name.clone());
 			},
-			(2606, "CONSTRAINT") => {
-				let table_name = item.tag.split_once(" ").unwrap().0;
-				filepath = vec![
-					item.namespace,
-					"TABLES".to_string(),
-					format!("{}.sql", &table_name),
-				];
+			(2606, "CONSTRAINT") |
+			(2606, "CHECK CONSTRAINT") |
+			(2606, "FK CONSTRAINT") |
+			(2604, "DEFAULT") |
+			(2620, "TRIGGER") |
+			(2618, "RULE") |
+			(3256, "POLICY") => {
+				let (table_name, name) = self.split_relation_tag(&item);
+				kind = self.relation_kind(&item.namespace, &table_name);
+				vars.insert("table", table_name);
+				vars.insert("name", name);
 			},
-			(2606, "CHECK CONSTRAINT") => {
-				let table_name = item.tag.split_once(" ").unwrap().0;
-				filepath = vec![
-					item.namespace,
-					"TABLES".to_string(),
-					format!("{}.sql", &table_name),
-				];
-			},
-			(2604, "DEFAULT") => {
-				let table_name = item.tag.split_once(" ").unwrap().0;
-				filepath = vec![
-					item.namespace,
-					"TABLES".to_string(),
-					format!("{}.sql", &table_name),
-				];
-			},
-			(2620, "TRIGGER") => {
-				let table_name = item.tag.split_once(" ").unwrap().0;
-				filepath = vec![
-					item.namespace,
-					"TABLES".to_string(),
-					format!("{}.sql", &table_name),
-				];
-			},
-			(2606, "FK CONSTRAINT") => {
-				let table_name = item.tag.split_once(" ").unwrap().0;
-				filepath = vec![
-					item.namespace,
-					"FK_CONSTRAINTS".to_string(),
-					format!("{}.sql", &table_name),
-				];
+			(0, "ROW SECURITY") => {
+				kind = self.relation_kind(&item.namespace, &item.tag);
+				vars.insert("table", item.tag.clone());
 			},
 			(1259, "SEQUENCE") => {
-				contents.push(
-					format!(
-						"ALTER SEQUENCE {}.{} OWNER TO {};\n",
-						&item.namespace,
-						&item.tag,
-						&item.owner,
-					),
-				);
-
-				filepath = vec![
-					item.namespace,
-					"SEQUENCES".to_string(),
-					format!("{}.sql", &item.tag),
-				];
-			},
-			(0, "SEQUENCE OWNED BY") => {
-				filepath = vec![
-					item.namespace,
-					"SEQUENCES".to_string(),
-					format!("{}.sql", &item.tag),
-				];
+				self.add_relation(&item, "sequence");
+				vars.insert("table", item.tag.clone());
 			},
 			(1259, "VIEW") => {
-				let hash_entry = View{
-					schema: item.namespace.clone(),
-					name: item.tag.clone(),
-				};
-				self.views.insert(hash_entry, ());
+				self.add_relation(&item, "view");
 
 				contents = vec![
-					format!("CREATE OR REPLACE VIEW {} AS", item.tag),
-					aux_data.pretty_printed_views.get(&item.oid).unwrap().to_string(),
+					render_view(&item, aux_data.views.get(&item.oid).unwrap()),
 				];
 
-				contents.push(
-					format!(
-						"ALTER VIEW {}.{} OWNER TO {};\n",
-						&item.namespace,
-						&item.tag,
-						&item.owner,
-					),
-				);
-
-				filepath = vec![
-					item.namespace,
-					"VIEWS".to_string(),
-					format!("{}.sql", &item.tag),
-				];
+				vars.insert("table", item.tag.clone());
 			},
-			(2618, "RULE") => {
-				filepath = vec![
-					item.namespace,
-					"RULES".to_string(),
-					format!("{}.sql", &item.tag),
-				];
+			(1259, "MATERIALIZED VIEW") => {
+				self.add_relation(&item, "materialized_view");
+				vars.insert("table", item.tag.clone());
+			},
+			(1259, "FOREIGN TABLE") => {
+				self.add_relation(&item, "foreign_table");
+				vars.insert("table", item.tag.clone());
+			},
+			(0, "TABLE ATTACH") | (0, "INDEX ATTACH") => {
+				// These go with the partition they attach, which is the first
+				// thing they depend on.
+				filepath = Some(match self.get_filepath_from_dependencies(&item) {
+					None => {
+						let err = DumpReadError::OtherError(
+							format!("could not find the partition for {} item {:?}", item.desc, item.tag),
+						);
+						self.unclassified(&item, err, options)?
+					},
+					Some(filepath) => filepath,
+				});
 			},
 			(6104, "PUBLICATION") => {
-				filepath = vec![
-					"PUBLICATIONS".to_string(),
-					item.tag.to_string(),
-					format!("{}.sql", &item.tag),
-				];
+				self.publications_by_dump_id.insert(item.dump_id, item.tag.clone());
+				vars.insert("publication", item.tag.clone());
 			},
-			(6106, "PUBLICATION TABLE") => {
-				let (publication_name, table_name) = item.tag.split_once(" ").unwrap();
-
-				filepath = vec![
-					"PUBLICATIONS".to_string(),
-					publication_name.to_string(),
-					format!("{}.sql", &table_name),
-				];
+			(6106, "PUBLICATION TABLE") |
+			(6237, "PUBLICATION TABLES IN SCHEMA") => {
+				// The tag looks like "publication table" or "publication
+				// schema".
+				let publication_name = self.get_publication_from_dependencies(&item);
+				let rest = publication_name.as_ref().and_then(|name| {
+					item.tag.strip_prefix(name.as_str()).and_then(|rest| rest.strip_prefix(' '))
+				});
+				match (publication_name, rest) {
+					(Some(publication_name), Some(rest)) => {
+						if item.desc == "PUBLICATION TABLE" {
+							vars.insert("table", rest.to_string());
+						}
+						vars.insert("publication", publication_name);
+					},
+					_ => {
+						let err = DumpReadError::OtherError(format!("invalid {} tag {:?}", item.desc, item.tag));
+						filepath = Some(self.unclassified(&item, err, options)?);
+					},
+				}
+			},
+			(0, "USER MAPPING") => {
+				// The tag looks like "USER MAPPING username SERVER servername".
+				match item.tag.rsplit_once(" SERVER ") {
+					None => {
+						let err = DumpReadError::OtherError(format!("invalid USER MAPPING tag {:?}", item.tag));
+						filepath = Some(self.unclassified(&item, err, options)?);
+					},
+					Some((_, server_name)) => {
+						vars.insert("server", server_name.to_string());
+					},
+				};
 			},
 			_ => {
-				panic!("unknown table_oid / desc for item {:?}", item);
+				// Nothing special about these.
 			},
 		}
 
+		// pg_dump leaves setting the owner of an object to pg_restore, so we
+		// have to do it ourselves.
+		if !options.no_owner {
+			if let Some(owner_statement) = owner_statement(&item) {
+				contents.push(owner_statement);
+			}
+		}
+
+		let filepath = match filepath {
+			Some(filepath) => filepath,
+			None => match options.layout.filepath(&item.desc, kind, &vars) {
+				Err(err) => self.unclassified(&item, DumpReadError::OtherError(err), options)?,
+				Ok(filepath) => filepath,
+			},
+		};
+		let mut filepath = self.resolve_path_collisions(filepath, options)?;
+
+		self.item_paths.insert(item.dump_id, filepath.clone());
+
 		if filepath.len() >= 1 {
 			let filepath_str = filepath.join("/");
+
+			let annotation = item.desc == "ACL" || item.desc == "COMMENT" || item.desc == "SECURITY LABEL";
+			if filepath_str != "index.sql" && !annotation {
+				self.manifest.push(
+					[filepath_str.as_str(), &item.desc, &item.namespace, &item.tag]
+						.iter()
+						.map(|field| escape_manifest_field(field))
+						.collect::<Vec<String>>()
+						.join("\t"),
+				);
+			}
+
 			let filename = filepath.pop().unwrap();
 			let mut cwd = &mut self.split_root;
 			for dir in filepath.iter() {
@@ -399,7 +705,7 @@ impl CustomDump {
 					cwd.files.insert(filename.clone(), contents);
 
 					if filename != "index.sql" {
-						self.split_root.files.get_mut("index.sql").unwrap().push(format!("\\ir {}", &filepath_str));
+						self.split_root.files.get_mut("index.sql").unwrap().push(format!("\\ir {}", psql_quote_path(&filepath_str)));
 					}
 				},
 				Some(vec) => {
@@ -411,85 +717,285 @@ impl CustomDump {
 		Ok(())
 	}
 
-	// A "combo tag", e.g. "SCHEMA public".
-	fn get_filepath_from_combo_tag(&mut self, item: &CustomDumpItem, typ: &str) -> Vec<String> {
-		let parts = item.tag.split_once(" ");
-		let (desc, rest) = match parts {
-			None => {
-				panic!("invalid tag {:?}", item.tag);
-			},
-			Some(tup) => tup,
+	// Deals with an item we couldn't figure out where to put.  Unless we're
+	// being strict about it, it goes into UNCLASSIFIED/<desc>/<tag>.sql so
+	// that e.g. an object type introduced in a new PostgreSQL version doesn't
+	// prevent the rest of the schema from being split.
+	fn unclassified(&mut self, item: &CustomDumpItem, err: DumpReadError, options: &SplitOptions) -> Result<Vec<String>, DumpReadError> {
+		if options.strict {
+			return Err(err);
+		}
+
+		self.warnings.push(format!("{}; writing it into UNCLASSIFIED/", err));
+		self.unclassified_items += 1;
+
+		Ok(vec![
+			"UNCLASSIFIED".to_string(),
+			shorten_path_component(escape_path_component(&item.desc)),
+			shorten_path_component(format!("{}.sql", escape_path_component(&item.tag))),
+		])
+	}
+
+	// Makes sure no two different names in the same directory refer to the
+	// same file on a file system which ignores case or Unicode normalization,
+	// e.g. tables "Users" and users.  The first name to claim a spot keeps it,
+	// and any name colliding with it later on gets a hash of itself appended,
+	// so the result only depends on the contents of the dump.
+	fn resolve_path_collisions(&mut self, filepath: Vec<String>, options: &SplitOptions) -> Result<Vec<String>, DumpReadError> {
+		let mut resolved: Vec<String> = vec![];
+		let ncomponents = filepath.len();
+		for (i, component) in filepath.into_iter().enumerate() {
+			let is_dir = i + 1 < ncomponents;
+			let parent = resolved.join("/");
+
+			let original_key = format!("{}/{}", parent, component);
+			if let Some(renamed) = self.renamed_path_components.get(&original_key) {
+				resolved.push(renamed.clone());
+				continue;
+			}
+
+			let folded_key = format!("{}/{}", parent, fold_path_component(&component));
+			match self.path_names.get(&folded_key).cloned() {
+				None => {
+					self.path_names.insert(folded_key, (component.clone(), is_dir));
+					resolved.push(component);
+				},
+				Some((existing, existing_is_dir)) if existing == component && existing_is_dir == is_dir => {
+					resolved.push(component);
+				},
+				Some((existing, _)) => {
+					let existing_path = if parent.is_empty() { existing } else { format!("{}/{}", parent, existing) };
+					if options.fail_on_path_collisions {
+						return Err(DumpReadError::OtherError(
+							format!("path {} collides with {} on case-insensitive file systems", original_key.trim_start_matches('/'), existing_path),
+						));
+					}
+
+					let renamed = disambiguate_path_component(&component);
+					self.warnings.push(format!(
+						"path {} collides with {} on case-insensitive file systems; using {} instead",
+						original_key.trim_start_matches('/'), existing_path, renamed,
+					));
+					self.path_names.insert(format!("{}/{}", parent, fold_path_component(&renamed)), (renamed.clone(), is_dir));
+					self.renamed_path_components.insert(original_key, renamed.clone());
+					resolved.push(renamed);
+				},
+			}
+		}
+		Ok(resolved)
+	}
+
+	fn add_manifest(&mut self) {
+		let mut manifest = vec![];
+		std::mem::swap(&mut manifest, &mut self.manifest);
+		// Keep the extra empty line at the end, like in the rest of the files.
+		manifest.push(String::new());
+		self.split_root.files.insert("manifest.tsv".to_string(), manifest);
+	}
+
+	fn add_relation(&mut self, item: &CustomDumpItem, kind: &'static str) {
+		let relation = Relation{
+			schema: item.namespace.clone(),
+			name: item.tag.clone(),
 		};
+		self.relation_kinds.insert(relation.clone(), kind);
+		self.relations_by_dump_id.insert(item.dump_id, relation);
+	}
 
-		match desc {
-			"SCHEMA" => {
-				return vec![
-					"SCHEMAS".to_string(),
-					format!("{}.sql", rest),
-				];
+	fn relation_kind(&self, schema: &str, name: &str) -> Option<&'static str> {
+		let relation = Relation{
+			schema: schema.to_string(),
+			name: name.to_string(),
+		};
+		self.relation_kinds.get(&relation).copied()
+	}
+
+	// Splits the tag of an object belonging to a relation, such as a
+	// constraint or a trigger, into the name of the relation and the name of
+	// the object.  The tags look like "tablename objectname", but either name
+	// could contain spaces, so we look for the relation among the item's
+	// dependencies before resorting to guessing.
+	fn split_relation_tag(&self, item: &CustomDumpItem) -> (String, String) {
+		for dep in &item.dependencies {
+			let relation = match self.relations_by_dump_id.get(dep) {
+				None => continue,
+				Some(relation) => relation,
+			};
+			if relation.schema != item.namespace {
+				continue;
+			}
+			let name = item.tag.strip_prefix(&relation.name).and_then(|rest| rest.strip_prefix(' '));
+			if let Some(name) = name {
+				return (relation.name.clone(), name.to_string());
+			}
+		}
+
+		match item.tag.split_once(" ") {
+			None => (item.tag.clone(), String::new()),
+			Some((table_name, name)) => (table_name.to_string(), name.to_string()),
+		}
+	}
+
+	// Returns the name of the publication the item depends on, if any.
+	fn get_publication_from_dependencies(&self, item: &CustomDumpItem) -> Option<String> {
+		item.dependencies.iter().find_map(|dep| self.publications_by_dump_id.get(dep).cloned())
+	}
+
+	// Returns the path of the first object the item depends on which has
+	// already been written somewhere.
+	fn get_filepath_from_dependencies(&self, item: &CustomDumpItem) -> Option<Vec<String>> {
+		for dep in &item.dependencies {
+			if let Some(filepath) = self.item_paths.get(dep) {
+				if !filepath.is_empty() {
+					return Some(filepath.clone());
+				}
+			}
+		}
+		None
+	}
+
+	// Figures out the file for an item with a "combo tag", e.g.
+	// "SCHEMA public".  These are comments, ACLs and security labels, and
+	// they depend on the object they're attached to, so we can usually just
+	// put them into the same file as that object.  Failing that, we work out
+	// from the tag where the layout would have put the object.  Note that
+	// unlike in the tags of the objects themselves, identifiers in combo tags
+	// are quoted.
+	fn get_filepath_from_combo_tag(&self, item: &CustomDumpItem, typ: &str, options: &SplitOptions) -> Result<Vec<String>, DumpReadError> {
+		if let Some(filepath) = self.get_filepath_from_dependencies(item) {
+			return Ok(filepath);
+		}
+
+		// Object types consisting of more than one word need to be looked for
+		// before we can split the tag.
+		const MULTI_WORD_TYPES: [&str; 13] = [
+			"ACCESS METHOD",
+			"EVENT TRIGGER",
+			"FOREIGN DATA WRAPPER",
+			"FOREIGN SERVER",
+			"FOREIGN TABLE",
+			"LARGE OBJECT",
+			"MATERIALIZED VIEW",
+			"OPERATOR CLASS",
+			"OPERATOR FAMILY",
+			"TEXT SEARCH CONFIGURATION",
+			"TEXT SEARCH DICTIONARY",
+			"TEXT SEARCH PARSER",
+			"TEXT SEARCH TEMPLATE",
+		];
+		let multi_word_type = MULTI_WORD_TYPES.iter().find(|desc| {
+			item.tag.starts_with(*desc) && item.tag[desc.len()..].starts_with(' ')
+		});
+		let (desc, rest) = match multi_word_type {
+			Some(desc) => (*desc, &item.tag[desc.len() + 1..]),
+			None => match item.tag.split_once(" ") {
+				None => {
+					return Err(DumpReadError::OtherError(format!("invalid tag {:?}", item.tag)));
+				},
+				Some(tup) => tup,
 			},
-			"EXTENSION" => {
-				return vec![
-					"EXTENSIONS".to_string(),
-					format!("{}.sql", rest),
-				];
+		};
+
+		let mut vars = PathVariables::new();
+		vars.insert("schema", item.namespace.clone());
+		vars.insert("name", unquote_identifier(rest));
+		vars.insert("tag", rest.to_string());
+		let mut kind = None;
+
+		let desc = match desc {
+			"DATABASE" => {
+				// We don't dump the database itself, either.
+				return Ok(vec![]);
 			},
-			"TYPE" => {
-				return vec![
-					item.namespace.clone(),
-					"TYPES".to_string(),
-					format!("{}.sql", rest),
-				];
+			"SCHEMA" => {
+				if unquote_identifier(rest) == "public" {
+					kind = Some("public");
+				}
+				desc
 			},
-			"FUNCTION" => {
-				let function_name = rest.split_once("(").unwrap().0;
-				return vec![
-					item.namespace.clone(),
-					"FUNCTIONS".to_string(),
-					format!("{}.sql", function_name),
-				];
+			"FUNCTION" | "PROCEDURE" | "AGGREGATE" => {
+				// The argument list in the tag has the names of the arguments
+				// in it, so we can't do anything about the signature.
+				let function_name = parse_identifier(rest).0;
+				if !options.split_function_overloads {
+					vars.insert("function", function_name.clone());
+				}
+				vars.insert("name", function_name);
+				desc
 			},
-			"TABLE" => {
+			"TABLE" | "COLUMN" => {
+				// For columns, this gets us the table name.
+				let table_name = parse_identifier(rest).0;
+
 				// ACLs don't know whether they're for a table or a view, so we
 				// need to figure that out here.
-				let subdir;
-				if self.is_view(&item.namespace, rest) {
-					subdir = "VIEWS";
+				let desc = match self.relation_kind(&item.namespace, &table_name) {
+					Some("view") => "VIEW",
+					Some("materialized_view") => "MATERIALIZED VIEW",
+					Some("foreign_table") => "FOREIGN TABLE",
+					Some("sequence") => "SEQUENCE",
+					_ => "TABLE",
+				};
+				vars.insert("name", table_name.clone());
+				vars.insert("table", table_name);
+				desc
+			},
+			"SEQUENCE" | "VIEW" | "MATERIALIZED VIEW" | "FOREIGN TABLE" => {
+				vars.insert("table", unquote_identifier(rest));
+				desc
+			},
+			"CONSTRAINT" | "TRIGGER" | "POLICY" | "RULE" => {
+				// E.g. "CONSTRAINT foo ON bar" or "CONSTRAINT foo ON DOMAIN bar".
+				let (name, target) = match split_once_unquoted(rest, " ON ") {
+					None => {
+						return Err(DumpReadError::OtherError(format!("invalid tag {:?}", item.tag)));
+					},
+					Some(tup) => tup,
+				};
+				if let Some(domain_name) = target.strip_prefix("DOMAIN ") {
+					vars.insert("name", unquote_identifier(domain_name));
+					"DOMAIN"
 				} else {
-					subdir = "TABLES";
+					let table_name = unquote_identifier(target);
+					kind = self.relation_kind(&item.namespace, &table_name);
+					vars.insert("name", unquote_identifier(name));
+					vars.insert("table", table_name);
+					desc
 				}
-				return vec![
-					item.namespace.clone(),
-					subdir.to_string(),
-					format!("{}.sql", rest),
-				];
 			},
-			"COLUMN" => {
-				let table_name = rest.split_once(".").unwrap().0;
-				return vec![
-					item.namespace.clone(),
-					"TABLES".to_string(),
-					format!("{}.sql", table_name),
-				];
+			"OPERATOR" => desc,
+			"OPERATOR CLASS" | "OPERATOR FAMILY" => {
+				// E.g. "OPERATOR CLASS foo USING btree"
+				vars.insert("name", parse_identifier(rest).0);
+				desc
 			},
-			"SEQUENCE" => {
-				return vec![
-					item.namespace.clone(),
-					"SEQUENCES".to_string(),
-					format!("{}.sql", rest),
-				];
+			"CAST" | "TRANSFORM" => {
+				vars.insert("tag", item.tag.clone());
+				desc
 			},
-			"VIEW" => {
-				return vec![
-					item.namespace.clone(),
-					"VIEWS".to_string(),
-					format!("{}.sql", rest),
-				];
+			"LANGUAGE" => "PROCEDURAL LANGUAGE",
+			"FOREIGN SERVER" => "SERVER",
+			"PUBLICATION" => {
+				vars.insert("publication", unquote_identifier(rest));
+				desc
 			},
-			_ => {
-				panic!("unknown desc {} for {} item {:?}", desc, typ, item);
+			"INDEX" => {
+				// Without the dependency we have no way of knowing which
+				// table the index belongs to.
+				return Err(
+					DumpReadError::OtherError(
+						format!("could not find the index for {} item {:?}", typ, item.tag),
+					),
+				);
 			},
+			_ => desc,
 		};
+		vars.insert("desc", desc.to_string());
+
+		match options.layout.filepath(desc, kind, &vars) {
+			Err(err) => Err(DumpReadError::OtherError(format!("{} item {:?}: {}", typ, item.tag, err))),
+			Ok(filepath) => Ok(filepath),
+		}
 	}
 
 	// Sorts a string of ACL entries.  The unsorted order can be difficult to
@@ -518,14 +1024,6 @@ impl CustomDump {
 
 		return parts;
 	}
-
-	fn is_view(&self, schema: &str, pg_class_entry: &str) -> bool {
-		let hash_entry = View{
-			schema: schema.to_string(),
-			name: pg_class_entry.to_string(),
-		};
-		return self.views.get(&hash_entry).is_some();
-	}
 }
 
 #[derive(Debug)]
@@ -641,13 +1139,12 @@ where
 		for _i in 0..len {
 			v.push(self.reader.read_u8()?);
 		}
-		let s = String::from_utf8(v).expect("file must be valid UTF-8");
-		Ok(s)
+		String::from_utf8(v).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("invalid UTF-8 in string: {}", e)))
 	}
 
 	fn read_oid_str(&mut self) -> io::Result<u32> {
 		let oid = self.read_str()?;
-		return Ok(oid.parse::<u32>().unwrap());
+		return oid.parse::<u32>().map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("invalid OID {:?}", oid)));
 	}
 
 	fn dump_version(&self) -> (u8, u8) {
@@ -655,7 +1152,7 @@ where
 	}
 
 	fn read_item(&mut self) -> io::Result<CustomDumpItem> {
-		let _dump_id = self.read_int()?;
+		let dump_id = self.read_int()?;
 		let _data_dumper = self.read_int()?;
 		let table_oid = self.read_oid_str()?;
 		let oid = self.read_oid_str()?;
@@ -663,7 +1160,7 @@ where
 		let desc = self.read_str()?;
 		let _section = self.read_int()?;
 		let definition = self.read_str()?;
-		let _drop_stmt = self.read_str()?;
+		let drop_stmt = self.read_str()?;
 		let _copy_stmt = self.read_str()?;
 		let namespace = self.read_str()?;
 		let _tablespace = self.read_str()?;
@@ -676,20 +1173,28 @@ where
 		let owner = self.read_str()?;
 		let _with_oids = self.read_str()?;
 
+		let mut dependencies = vec![];
 		loop {
-			let _dep = self.read_str()?;
-			if _dep == "" {
+			let dep = self.read_str()?;
+			if dep.is_empty() {
 				break;
 			}
+			match dep.parse::<i64>() {
+				Err(_) => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("invalid dependency {:?}", dep))),
+				Ok(dep) => dependencies.push(dep),
+			}
 		}
 		let _offset = self.read_offset();
 
 		Ok(CustomDumpItem{
+			dump_id,
+			dependencies,
 			table_oid: table_oid,
 			oid: oid,
 			tag: tag,
 			desc: desc,
 			definition: definition,
+			drop_stmt,
 			namespace: namespace,
 			owner: owner,
 		})
@@ -744,11 +1249,14 @@ struct CustomDumpHeader {
 
 #[derive(Debug)]
 pub struct CustomDumpItem {
+	pub dump_id: i64,
+	pub dependencies: Vec<i64>,
 	pub table_oid: u32,
 	pub oid: u32,
 	pub tag: String,
 	pub desc: String,
 	pub definition: String,
+	pub drop_stmt: String,
 	pub namespace: String,
 	pub owner: String,
 }
@@ -775,3 +1283,32 @@ where
 		Some(item)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn known_items_are_unique() {
+		let mut descs: Vec<&str> = known_descs().collect();
+		descs.dedup();
+		assert_eq!(descs.len(), KNOWN_ITEMS.len());
+		assert!(descs.windows(2).all(|pair| pair[0] < pair[1]));
+	}
+
+	#[test]
+	fn default_layout_places_every_known_desc() {
+		let mut vars = PathVariables::new();
+		for variable in ["schema", "name", "table", "function", "signature", "publication", "server", "desc", "tag"] {
+			vars.insert(variable, "x".to_string());
+		}
+		let layout = Layout::default();
+		for desc in known_descs() {
+			// These go wherever the object they depend on went.
+			if ["ACL", "COMMENT", "SECURITY LABEL", "TABLE ATTACH", "INDEX ATTACH"].contains(&desc) {
+				continue;
+			}
+			assert!(layout.filepath(desc, None, &vars).is_ok(), "the default layout has no path for {}", desc);
+		}
+	}
+}
@@ -0,0 +1,468 @@
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+
+use crate::auxiliary_data::AuxiliaryData;
+use crate::custom_dump_reader::CustomDumpItem;
+
+// Characters which have a special meaning in a regular expression.
+const REGEX_SPECIAL_CHARACTERS: &str = "|*+?()[]{}.^$\\";
+
+// A name pattern as understood by psql's \d and pg_dump's --schema and
+// --table, translated into regular expressions the same way psql's
+// patternToSQLRegex() does: "*" and "?" are wildcards, other regular
+// expression syntax (e.g. "(a|b)") can be used as is, unquoted letters are
+// folded to lower case, double quotes protect all of those, and a "."
+// separates the schema from the name of an object.  Every part has to match
+// the whole name.
+#[derive(Debug, Clone)]
+pub struct NamePattern {
+	pub pattern: String,
+	schema: Option<Regex>,
+	name: Regex,
+}
+
+// Translates a pattern into one regular expression per dot-separated part.
+fn pattern_to_regexes(pattern: &str) -> Result<Vec<String>, String> {
+	let mut parts = vec![String::new()];
+	let mut in_quotes = false;
+	let mut chars = pattern.chars().peekable();
+	while let Some(c) = chars.next() {
+		let part = parts.last_mut().unwrap();
+		if c == '"' {
+			if in_quotes && chars.peek() == Some(&'"') {
+				part.push('"');
+				chars.next();
+			} else {
+				in_quotes = !in_quotes;
+			}
+		} else if !in_quotes && c.is_ascii_uppercase() {
+			part.push(c.to_ascii_lowercase());
+		} else if !in_quotes && c == '*' {
+			part.push_str(".*");
+		} else if !in_quotes && c == '?' {
+			part.push('.');
+		} else if !in_quotes && c == '.' {
+			parts.push(String::new());
+		} else if c == '$' {
+			// Allowed in identifiers, and useless as an anchor since the
+			// pattern is anchored anyway.
+			part.push_str("\\$");
+		} else {
+			// Outside quotes regular expression syntax is passed through,
+			// except for "[]", which is much more likely to be part of an
+			// array type's name than a bracket expression.
+			if (in_quotes && REGEX_SPECIAL_CHARACTERS.contains(c)) || (c == '[' && chars.peek() == Some(&']')) {
+				part.push('\\');
+			}
+			part.push(c);
+		}
+	}
+	if in_quotes {
+		return Err(format!("unterminated quoted identifier in pattern {:?}", pattern));
+	}
+	Ok(parts)
+}
+
+fn compile_regex(part: &str, pattern: &str) -> Result<Regex, String> {
+	match Regex::new(&format!("^({})$", part)) {
+		Err(err) => Err(format!("invalid pattern {:?}: {}", pattern, err)),
+		Ok(regex) => Ok(regex),
+	}
+}
+
+impl NamePattern {
+	pub fn parse(pattern: &str, qualified: bool) -> Result<NamePattern, String> {
+		let mut parts = pattern_to_regexes(pattern)?;
+
+		let max_parts = if qualified { 2 } else { 1 };
+		if parts.len() > max_parts {
+			return Err(format!("improper qualified name (too many dotted names): {}", pattern));
+		}
+
+		let name = compile_regex(&parts.pop().unwrap(), pattern)?;
+		let schema = match parts.pop() {
+			None => None,
+			Some(part) => Some(compile_regex(&part, pattern)?),
+		};
+		Ok(NamePattern{
+			pattern: pattern.to_string(),
+			schema,
+			name,
+		})
+	}
+
+	// Checks whether the pattern matches an object; an unqualified pattern
+	// matches objects in any schema.
+	pub fn matches(&self, schema: &str, name: &str) -> bool {
+		if let Some(schema_pattern) = &self.schema {
+			if !schema_pattern.is_match(schema) {
+				return false;
+			}
+		}
+		self.name.is_match(name)
+	}
+}
+
+// Which objects to include in the output, and which to leave out.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectFilter {
+	pub include_schemas: Vec<NamePattern>,
+	pub exclude_schemas: Vec<NamePattern>,
+	pub include_tables: Vec<NamePattern>,
+	pub exclude_tables: Vec<NamePattern>,
+	// TOC entry descs, e.g. "FUNCTION" or "MATERIALIZED VIEW".
+	pub only_kinds: Vec<String>,
+	pub exclude_kinds: Vec<String>,
+	// Leave out objects which belong to an extension, and anything in the
+	// schemas an extension created for itself.
+	pub exclude_extension_members: bool,
+	// Names of extensions whose schemas (the ones they created) should be
+	// left out entirely.
+	pub exclude_extension_schemas: Vec<String>,
+}
+
+fn any_matches(patterns: &[NamePattern], schema: &str, name: &str) -> bool {
+	patterns.iter().any(|pattern| pattern.matches(schema, name))
+}
+
+// Descs of relations, which --table and --exclude-table apply to.
+const RELATION_DESCS: [&str; 5] = [
+	"TABLE",
+	"VIEW",
+	"MATERIALIZED VIEW",
+	"SEQUENCE",
+	"FOREIGN TABLE",
+];
+
+// Descs of objects which belong to a relation, and go wherever the relation
+// goes.
+const RELATION_SUBOBJECT_DESCS: [&str; 14] = [
+	"INDEX",
+	"INDEX ATTACH",
+	"CONSTRAINT",
+	"CHECK CONSTRAINT",
+	"FK CONSTRAINT",
+	"DEFAULT",
+	"TRIGGER",
+	"RULE",
+	"POLICY",
+	"ROW SECURITY",
+	"SEQUENCE OWNED BY",
+	"SEQUENCE SET",
+	"TABLE ATTACH",
+	"STATISTICS",
+];
+
+// Entries which only annotate another object.
+const ANNOTATION_DESCS: [&str; 3] = [
+	"ACL",
+	"COMMENT",
+	"SECURITY LABEL",
+];
+
+// Entries which aren't objects but settings for the whole dump, and are
+// never filtered out.
+const SETTING_DESCS: [&str; 3] = [
+	"ENCODING",
+	"STDSTRINGS",
+	"SEARCHPATH",
+];
+
+impl ObjectFilter {
+	// Returns the arguments to pass to pg_dump to have it apply the filter.
+	pub fn pg_dump_args(&self) -> Vec<String> {
+		let mut args = vec![];
+		for pattern in &self.include_schemas {
+			args.push(format!("--schema={}", pattern.pattern));
+		}
+		for pattern in &self.exclude_schemas {
+			args.push(format!("--exclude-schema={}", pattern.pattern));
+		}
+		for pattern in &self.include_tables {
+			args.push(format!("--table={}", pattern.pattern));
+		}
+		for pattern in &self.exclude_tables {
+			args.push(format!("--exclude-table={}", pattern.pattern));
+		}
+		args
+	}
+
+	// Whether anything outside of what the include patterns match is left
+	// out, like pg_dump does when given --schema or --table.
+	fn includes_everything(&self) -> bool {
+		self.include_schemas.is_empty() && self.include_tables.is_empty()
+	}
+
+	fn includes_kind(&self, desc: &str) -> bool {
+		if self.exclude_kinds.iter().any(|kind| kind == desc) {
+			return false;
+		}
+		self.only_kinds.is_empty() || self.only_kinds.iter().any(|kind| kind == desc)
+	}
+
+	fn includes_schema(&self, schema: &str) -> bool {
+		if !self.include_schemas.is_empty() && !any_matches(&self.include_schemas, "", schema) {
+			return false;
+		}
+		!any_matches(&self.exclude_schemas, "", schema)
+	}
+
+	// Returns the schemas created by the extensions in
+	// exclude_extension_schemas, or an error if one of those doesn't exist or
+	// didn't create any schemas.
+	pub fn excluded_extension_schemas<'a>(&self, aux_data: &'a AuxiliaryData) -> Result<HashSet<&'a str>, String> {
+		let mut excluded_extension_schemas = HashSet::new();
+		for extname in &self.exclude_extension_schemas {
+			if !aux_data.extension_schemas.contains_key(extname) {
+				return Err(format!("extension {:?} does not exist", extname));
+			}
+			// Only the schemas the extension created itself; leaving out the
+			// one it merely got installed into, such as public, would take
+			// the user's own objects with it.
+			let mut created_schemas = 0;
+			for (schema, member_of) in &aux_data.extension_member_schemas {
+				if member_of == extname {
+					excluded_extension_schemas.insert(schema.as_str());
+					created_schemas += 1;
+				}
+			}
+			if created_schemas == 0 {
+				return Err(format!(
+					"extension {:?} did not create any schemas; use --exclude-schema to leave out the schema it is installed into",
+					extname,
+				));
+			}
+		}
+		Ok(excluded_extension_schemas)
+	}
+
+	fn includes_relation(&self, schema: &str, name: &str) -> bool {
+		if any_matches(&self.exclude_tables, schema, name) {
+			return false;
+		}
+		// As with pg_dump, the schema patterns don't matter if any tables
+		// were asked for explicitly.
+		if !self.include_tables.is_empty() {
+			return any_matches(&self.include_tables, schema, name);
+		}
+		self.includes_schema(schema)
+	}
+}
+
+// Applies an ObjectFilter to the entries of a dump, in dump order.  Entries
+// belonging to an object which has been filtered out (its comments, the
+// indexes of a table etc.) are filtered out along with it.
+pub struct ItemFilter<'a> {
+	filter: &'a ObjectFilter,
+	aux_data: &'a AuxiliaryData,
+	// Schemas created by the extensions in filter.exclude_extension_schemas.
+	excluded_extension_schemas: HashSet<&'a str>,
+	// Dump ids of all entries seen and of the ones filtered out so far.
+	seen: HashSet<i64>,
+	excluded: HashSet<i64>,
+	// Dump ids of relations -> whether they were filtered out by name (or
+	// as part of an extension), rather than because of their kind.
+	relations: HashMap<i64, bool>,
+}
+
+impl<'a> ItemFilter<'a> {
+	pub fn new(filter: &'a ObjectFilter, aux_data: &'a AuxiliaryData) -> Result<ItemFilter<'a>, String> {
+		let excluded_extension_schemas = filter.excluded_extension_schemas(aux_data)?;
+
+		Ok(ItemFilter{
+			filter,
+			aux_data,
+			excluded_extension_schemas,
+			seen: HashSet::new(),
+			excluded: HashSet::new(),
+			relations: HashMap::new(),
+		})
+	}
+
+	fn belongs_to_excluded_extension(&self, item: &CustomDumpItem) -> bool {
+		if item.desc == "EXTENSION" {
+			// Always keep the CREATE EXTENSION itself.
+			return false;
+		}
+
+		let schema = if item.desc == "SCHEMA" { &item.tag } else { &item.namespace };
+		if self.excluded_extension_schemas.contains(schema.as_str()) {
+			return true;
+		}
+
+		if self.filter.exclude_extension_members {
+			// pg_dump doesn't dump the members of an extension, but it does
+			// dump their ACLs if they've been changed since the extension was
+			// created.  Those are the only entries for objects which aren't
+			// in the dump.
+			if ANNOTATION_DESCS.contains(&item.desc.as_str()) &&
+				!item.dependencies.is_empty() &&
+				item.dependencies.iter().all(|dep| !self.seen.contains(dep)) {
+				return true;
+			}
+			if self.aux_data.extension_members.contains_key(&(item.table_oid, item.oid)) {
+				return true;
+			}
+			if self.aux_data.extension_member_schemas.contains_key(schema) {
+				return true;
+			}
+		}
+		false
+	}
+
+	// Returns true if the item should be left out, and remembers that.
+	pub fn excludes(&mut self, item: &CustomDumpItem) -> bool {
+		let excluded = self.decide(item);
+		self.seen.insert(item.dump_id);
+		if excluded {
+			self.excluded.insert(item.dump_id);
+		}
+		excluded
+	}
+
+	fn decide(&mut self, item: &CustomDumpItem) -> bool {
+		let desc = item.desc.as_str();
+
+		if SETTING_DESCS.contains(&desc) {
+			return false;
+		}
+
+		if self.belongs_to_excluded_extension(item) {
+			if RELATION_DESCS.contains(&desc) {
+				self.relations.insert(item.dump_id, true);
+			}
+			return true;
+		}
+
+		// Comments, ACLs and security labels go wherever the object they're
+		// for goes, unless they're excluded explicitly.
+		let kind_excluded = if ANNOTATION_DESCS.contains(&desc) {
+			self.filter.exclude_kinds.iter().any(|kind| kind == desc)
+		} else {
+			!self.filter.includes_kind(desc)
+		};
+
+		if RELATION_DESCS.contains(&desc) {
+			// Only a relation left out by name takes its indexes, triggers
+			// etc. with it; leaving out a kind of object shouldn't affect
+			// any other kind, so that e.g. --only-kind=TRIGGER still gets
+			// the triggers of all tables.
+			let name_excluded = !self.filter.includes_relation(&item.namespace, &item.tag);
+			self.relations.insert(item.dump_id, name_excluded);
+			return kind_excluded || name_excluded;
+		}
+		if kind_excluded {
+			return true;
+		}
+		if ANNOTATION_DESCS.contains(&desc) || desc == "PUBLICATION TABLE" || desc == "PUBLICATION TABLES IN SCHEMA" {
+			return item.dependencies.iter().any(|dep| self.excluded.contains(dep));
+		}
+		if RELATION_SUBOBJECT_DESCS.contains(&desc) {
+			// Only look at the relation itself here; e.g. a foreign key
+			// also depends on the referenced table's primary key, but
+			// belongs to the referencing table.
+			let relation_excluded = item.dependencies.iter().any(|dep| self.relations.get(dep) == Some(&true));
+			if relation_excluded {
+				return true;
+			}
+		}
+
+		if desc == "SCHEMA" {
+			return !self.filter.include_tables.is_empty() || !self.filter.includes_schema(&item.tag);
+		}
+		if item.namespace.is_empty() {
+			// Objects not in any schema only make it if we're not being
+			// selective.
+			return !self.filter.includes_everything();
+		}
+		if !self.filter.include_tables.is_empty() {
+			// Only the tables asked for and the objects belonging to them.
+			return !RELATION_SUBOBJECT_DESCS.contains(&desc);
+		}
+		!self.filter.includes_schema(&item.namespace)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn matches(pattern: &str, name: &str) -> bool {
+		NamePattern::parse(pattern, false).unwrap().matches("", name)
+	}
+
+	#[test]
+	fn pattern_translation() {
+		assert_eq!(pattern_to_regexes("public").unwrap(), vec!["public"]);
+		assert_eq!(pattern_to_regexes("App_*.T?").unwrap(), vec!["app_.*", "t."]);
+		assert_eq!(pattern_to_regexes("\"A.b*\".c").unwrap(), vec!["A\\.b\\*", "c"]);
+		assert_eq!(pattern_to_regexes("app_(a|b)").unwrap(), vec!["app_(a|b)"]);
+		assert_eq!(pattern_to_regexes("x$").unwrap(), vec!["x\\$"]);
+		assert_eq!(pattern_to_regexes("t[]").unwrap(), vec!["t\\[]"]);
+		assert_eq!(pattern_to_regexes("\"a\"\"b\"").unwrap(), vec!["a\"b"]);
+	}
+
+	#[test]
+	fn wildcards_and_anchoring() {
+		assert!(matches("public", "public"));
+		assert!(!matches("public", "public2"));
+		assert!(!matches("public", "my_public"));
+		assert!(matches("*", "anything"));
+		assert!(matches("pub*", "public"));
+		assert!(matches("a?c", "abc"));
+		assert!(!matches("a?c", "ac"));
+	}
+
+	#[test]
+	fn regular_expressions() {
+		assert!(matches("app_(a|b)", "app_a"));
+		assert!(matches("app_(a|b)", "app_b"));
+		assert!(!matches("app_(a|b)", "app_c"));
+		assert!(matches("app_[0-9]+", "app_42"));
+		assert!(!matches("app_[0-9]+", "app_"));
+		assert!(matches("x{2}", "xx"));
+		assert!(matches("x$y", "x$y"));
+		assert!(matches("t[]", "t[]"));
+	}
+
+	#[test]
+	fn case_folding_and_quoting() {
+		assert!(matches("Users", "users"));
+		assert!(!matches("Users", "Users"));
+		assert!(matches("\"Users\"", "Users"));
+		assert!(!matches("\"Users\"", "users"));
+		// Like psql, only ASCII letters are folded.
+		assert!(matches("Ä", "Ä"));
+		assert!(!matches("Ä", "ä"));
+		assert!(matches("\"a.b\"", "a.b"));
+		assert!(!matches("\"a.b\"", "axb"));
+		assert!(matches("\"a*\"", "a*"));
+		assert!(!matches("\"a*\"", "ab"));
+		assert!(matches("\"(a|b)\"", "(a|b)"));
+		assert!(!matches("\"(a|b)\"", "a"));
+		assert!(matches("\"a\"\"b\"", "a\"b"));
+		assert!(matches("My\" \"Schema", "my schema"));
+		assert!(!matches("My\" \"Schema", "my Schema"));
+	}
+
+	#[test]
+	fn qualified_patterns() {
+		let pattern = NamePattern::parse("\"My Schema\".ord*", true).unwrap();
+		assert!(pattern.matches("My Schema", "order"));
+		assert!(!pattern.matches("public", "order"));
+		assert!(!pattern.matches("My Schema", "Order"));
+
+		let pattern = NamePattern::parse("ord*", true).unwrap();
+		assert!(pattern.matches("My Schema", "order"));
+		assert!(pattern.matches("public", "orders"));
+	}
+
+	#[test]
+	fn invalid_patterns() {
+		assert!(NamePattern::parse("\"unterminated", false).is_err());
+		assert!(NamePattern::parse("s.t", false).is_err());
+		assert!(NamePattern::parse("a.b.c", true).is_err());
+		assert!(NamePattern::parse("app_(a", false).is_err());
+	}
+}
@@ -0,0 +1,126 @@
+// Helpers for dealing with SQL identifiers as they appear in pg_dump's TOC
+// entries.  pg_dump quotes identifiers in "combo tags" (e.g. the tag of a
+// COMMENT entry), but not in the tags of the objects themselves, so we need
+// to be able to get from the former to the latter, and to quote identifiers
+// ourselves in any SQL we generate.
+
+// Keywords which can't be used as an identifier without quoting it, i.e. all
+// keywords except the unreserved ones.  Includes the ones added in later
+// PostgreSQL versions; quoting an identifier unnecessarily does no harm.
+// Must be kept sorted.
+const NON_UNRESERVED_KEYWORDS: [&str; 164] = [
+	"all", "analyse", "analyze", "and", "any", "array",
+	"as", "asc", "asymmetric", "authorization", "between", "bigint",
+	"binary", "bit", "boolean", "both", "case", "cast",
+	"char", "character", "check", "coalesce", "collate", "collation",
+	"column", "concurrently", "constraint", "create", "cross", "current_catalog",
+	"current_date", "current_role", "current_schema", "current_time", "current_timestamp", "current_user",
+	"dec", "decimal", "default", "deferrable", "desc", "distinct",
+	"do", "else", "end", "except", "exists", "extract",
+	"false", "fetch", "float", "for", "foreign", "freeze",
+	"from", "full", "grant", "greatest", "group", "grouping",
+	"having", "ilike", "in", "initially", "inner", "inout",
+	"int", "integer", "intersect", "interval", "into", "is",
+	"isnull", "join", "json", "json_array", "json_arrayagg", "json_exists",
+	"json_object", "json_objectagg", "json_query", "json_scalar", "json_serialize", "json_table",
+	"json_value", "lateral", "leading", "least", "left", "like",
+	"limit", "localtime", "localtimestamp", "merge_action", "national", "natural",
+	"nchar", "none", "normalize", "not", "notnull", "null",
+	"nullif", "numeric", "offset", "on", "only", "or",
+	"order", "out", "outer", "overlaps", "overlay", "placing",
+	"position", "precision", "primary", "real", "references", "returning",
+	"right", "row", "select", "session_user", "setof", "similar",
+	"smallint", "some", "substring", "symmetric", "system_user", "table",
+	"tablesample", "then", "time", "timestamp", "to", "trailing",
+	"treat", "trim", "true", "union", "unique", "user",
+	"using", "values", "varchar", "variadic", "verbose", "when",
+	"where", "window", "with", "xmlattributes", "xmlconcat", "xmlelement",
+	"xmlexists", "xmlforest", "xmlnamespaces", "xmlparse", "xmlpi", "xmlroot",
+	"xmlserialize", "xmltable",
+];
+
+// Reads a single, possibly double-quoted identifier from the beginning of s.
+// Returns the unquoted identifier and the remainder of the string.  An
+// unquoted identifier ends at the first character which couldn't be part of
+// one.
+pub fn parse_identifier(s: &str) -> (String, &str) {
+	let mut chars = s.char_indices().peekable();
+
+	if s.starts_with('"') {
+		let mut ident = String::new();
+		chars.next();
+		while let Some((_, c)) = chars.next() {
+			if c != '"' {
+				ident.push(c);
+				continue;
+			}
+			match chars.peek() {
+				Some((_, '"')) => {
+					ident.push('"');
+					chars.next();
+				},
+				Some((pos, _)) => {
+					return (ident, &s[*pos..]);
+				},
+				None => {
+					return (ident, "");
+				},
+			}
+		}
+		// Unterminated quoted identifier; take everything.
+		return (ident, "");
+	}
+
+	for (pos, c) in chars {
+		if c == '.' || c == '(' || c == ')' || c == ',' || c == ';' || c.is_whitespace() {
+			return (s[..pos].to_string(), &s[pos..]);
+		}
+	}
+	(s.to_string(), "")
+}
+
+// Splits s at the first occurrence of sep which isn't inside a quoted
+// identifier, a string literal or a parenthesized list.
+pub fn split_once_unquoted<'a>(s: &'a str, sep: &str) -> Option<(&'a str, &'a str)> {
+	let mut in_quotes = false;
+	let mut in_literal = false;
+	let mut depth = 0;
+	for (pos, c) in s.char_indices() {
+		if c == '"' && !in_literal {
+			in_quotes = !in_quotes;
+		} else if c == '\'' && !in_quotes {
+			in_literal = !in_literal;
+		} else if in_quotes || in_literal {
+			continue;
+		} else if c == '(' {
+			depth += 1;
+		} else if c == ')' {
+			depth -= 1;
+		} else if depth == 0 && s[pos..].starts_with(sep) {
+			return Some((&s[..pos], &s[pos + sep.len()..]));
+		}
+	}
+	None
+}
+
+// Unquotes a string consisting of a single identifier.
+pub fn unquote_identifier(s: &str) -> String {
+	parse_identifier(s).0
+}
+
+// Quotes an identifier if necessary, the same way PostgreSQL's
+// quote_ident() would.
+pub fn quote_identifier(ident: &str) -> String {
+	let safe = ident.starts_with(|c: char| c.is_ascii_lowercase() || c == '_') &&
+		ident.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') &&
+		NON_UNRESERVED_KEYWORDS.binary_search(&ident).is_err();
+	if safe {
+		return ident.to_string();
+	}
+	format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+// Returns schema.name with both parts quoted as necessary.
+pub fn quote_qualified_name(schema: &str, name: &str) -> String {
+	format!("{}.{}", quote_identifier(schema), quote_identifier(name))
+}
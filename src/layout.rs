@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::custom_dump_reader::known_descs;
+use crate::path_encoding::{escape_path_component, shorten_path_component};
+
+// See the comments in the file itself for the format.
+const DEFAULT_LAYOUT: &str = include_str!("default_layout.toml");
+
+// The variables path templates can refer to.
+const VARIABLES: [&str; 9] = [
+	"schema",
+	"name",
+	"table",
+	"function",
+	"signature",
+	"publication",
+	"server",
+	"desc",
+	"tag",
+];
+
+// Entries which go wherever the object they belong to went, and so can't be
+// given a path of their own.
+const DEPENDENT_DESCS: [&str; 5] = [
+	"ACL",
+	"COMMENT",
+	"SECURITY LABEL",
+	"TABLE ATTACH",
+	"INDEX ATTACH",
+];
+
+// The kinds of relation the objects belonging to one can be told apart by.
+const RELATION_KINDS: [&str; 4] = [
+	"table",
+	"view",
+	"materialized_view",
+	"foreign_table",
+];
+
+// Returns the kinds the key for desc can be qualified with.
+fn object_kinds(desc: &str) -> &'static [&'static str] {
+	match desc {
+		"SCHEMA" => &["public"],
+		"FUNCTION" => &["trigger"],
+		"INDEX" | "CONSTRAINT" | "CHECK CONSTRAINT" | "FK CONSTRAINT" | "DEFAULT" |
+		"TRIGGER" | "RULE" | "POLICY" | "ROW SECURITY" => &RELATION_KINDS,
+		_ => &[],
+	}
+}
+
+// Checks that a key in [paths] is a desc we know, optionally qualified with
+// a kind objects with that desc can have.
+fn check_key(key: &str) -> Result<(), String> {
+	let (desc, kind) = match key.split_once(':') {
+		None => (key, None),
+		Some((desc, kind)) => (desc, Some(kind)),
+	};
+	if !known_descs().any(|known_desc| known_desc == desc) {
+		return Err(format!("unknown desc {:?}", desc));
+	}
+	if DEPENDENT_DESCS.contains(&desc) {
+		return Err(format!("{} entries go with the object they belong to", desc));
+	}
+	if let Some(kind) = kind {
+		let kinds = object_kinds(desc);
+		if !kinds.contains(&kind) {
+			if kinds.is_empty() {
+				return Err(format!("{} can't be qualified with a kind", desc));
+			}
+			return Err(format!("unknown kind {:?} for {}; valid kinds are: {}", kind, desc, kinds.join(", ")));
+		}
+	}
+	Ok(())
+}
+
+// Values for the template variables of a single object.
+pub type PathVariables = HashMap<&'static str, String>;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayoutFile {
+	#[serde(default)]
+	paths: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+enum TemplatePart {
+	Literal(String),
+	Variable(String),
+}
+
+#[derive(Debug, Clone)]
+struct PathTemplate {
+	// One list of parts per path component.  Empty if the objects using the
+	// template shouldn't be written at all.
+	components: Vec<Vec<TemplatePart>>,
+}
+
+impl PathTemplate {
+	fn parse(template: &str) -> Result<PathTemplate, String> {
+		if template.is_empty() {
+			return Ok(PathTemplate{
+				components: vec![],
+			});
+		}
+		if template == "index.sql" || template == "manifest.tsv" {
+			return Err(format!("{} is reserved", template));
+		}
+
+		let mut components = vec![];
+		for component in template.split('/') {
+			if component.is_empty() || component == "." || component == ".." {
+				return Err(format!("invalid path component {:?}", component));
+			}
+
+			let mut parts = vec![];
+			let mut rest = component;
+			while let Some(start) = rest.find('{') {
+				if start > 0 {
+					parts.push(TemplatePart::Literal(rest[..start].to_string()));
+				}
+				let end = match rest[start..].find('}') {
+					None => return Err("unterminated variable reference".to_string()),
+					Some(end) => start + end,
+				};
+				let variable = &rest[start + 1..end];
+				if !VARIABLES.contains(&variable) {
+					return Err(format!("unknown variable {{{}}}", variable));
+				}
+				parts.push(TemplatePart::Variable(variable.to_string()));
+				rest = &rest[end + 1..];
+			}
+			if rest.contains('}') {
+				return Err("unmatched \"}\"".to_string());
+			}
+			if !rest.is_empty() {
+				parts.push(TemplatePart::Literal(rest.to_string()));
+			}
+			components.push(parts);
+		}
+
+		Ok(PathTemplate{
+			components,
+		})
+	}
+
+	fn render(&self, vars: &PathVariables) -> Result<Vec<String>, String> {
+		let mut filepath = vec![];
+		for parts in &self.components {
+			let mut component = String::new();
+			for part in parts {
+				match part {
+					TemplatePart::Literal(literal) => component.push_str(literal),
+					TemplatePart::Variable(variable) => match vars.get(variable.as_str()) {
+						None => return Err(format!("variable {{{}}} is not available", variable)),
+						Some(value) => component.push_str(&escape_path_component(value)),
+					},
+				}
+			}
+			// Leave out components which only had e.g. the (empty) schema of a
+			// global object in them.
+			if !component.is_empty() {
+				filepath.push(shorten_path_component(component));
+			}
+		}
+		if filepath.is_empty() && !self.components.is_empty() {
+			return Err("the path is empty".to_string());
+		}
+		Ok(filepath)
+	}
+}
+
+// Decides where in the output each object goes.
+#[derive(Debug, Clone)]
+pub struct Layout {
+	paths: HashMap<String, PathTemplate>,
+}
+
+impl Default for Layout {
+	fn default() -> Layout {
+		let mut layout = Layout{
+			paths: HashMap::new(),
+		};
+		layout.merge(DEFAULT_LAYOUT).expect("the default layout is invalid");
+		layout
+	}
+}
+
+impl Layout {
+	// Reads a layout file.  Anything the file doesn't say anything about is
+	// laid out the default way.
+	pub fn load(path: &Path) -> Result<Layout, String> {
+		let contents = match fs::read_to_string(path) {
+			Err(err) => {
+				return Err(format!("could not read layout file {}: {}", path.display(), err));
+			},
+			Ok(contents) => contents,
+		};
+
+		let mut layout = Layout::default();
+		if let Err(err) = layout.merge(&contents) {
+			return Err(format!("invalid layout file {}: {}", path.display(), err));
+		}
+		Ok(layout)
+	}
+
+	fn merge(&mut self, contents: &str) -> Result<(), String> {
+		let file: LayoutFile = match toml::from_str(contents) {
+			Err(err) => return Err(err.to_string()),
+			Ok(file) => file,
+		};
+
+		// Overriding the path of an object type also overrides the paths of
+		// the specific kinds of it, unless those are overridden as well.
+		for key in file.paths.keys() {
+			if !key.contains(':') {
+				let prefix = format!("{}:", key);
+				self.paths.retain(|existing_key, _| !existing_key.starts_with(&prefix));
+			}
+		}
+
+		for (key, template) in file.paths {
+			if let Err(err) = check_key(&key) {
+				return Err(format!("invalid key {:?}: {}", key, err));
+			}
+			let template = match PathTemplate::parse(&template) {
+				Err(err) => return Err(format!("invalid path template for {:?}: {}", key, err)),
+				Ok(template) => template,
+			};
+			self.paths.insert(key, template);
+		}
+		Ok(())
+	}
+
+	// Returns the path for an object with the given desc, as a list of path
+	// components.  An empty list means the object should be left out.
+	pub fn filepath(&self, desc: &str, kind: Option<&str>, vars: &PathVariables) -> Result<Vec<String>, String> {
+		let mut template = None;
+		if let Some(kind) = kind {
+			template = self.paths.get(&format!("{}:{}", desc, kind));
+		}
+		let template = match template.or_else(|| self.paths.get(desc)) {
+			None => return Err(format!("the layout has no path for {}", desc)),
+			Some(template) => template,
+		};
+
+		match template.render(vars) {
+			Err(err) => Err(format!("could not build the path for {} {:?}: {}", desc, vars.get("tag").map(String::as_str).unwrap_or(""), err)),
+			Ok(filepath) => Ok(filepath),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn vars(pairs: &[(&'static str, &str)]) -> PathVariables {
+		pairs.iter().map(|(name, value)| (*name, value.to_string())).collect()
+	}
+
+	fn layout(contents: &str) -> Result<Layout, String> {
+		let mut layout = Layout::default();
+		layout.merge(contents)?;
+		Ok(layout)
+	}
+
+	#[test]
+	fn default_layout() {
+		let layout = Layout::default();
+		let table = vars(&[("schema", "public"), ("name", "t"), ("table", "t")]);
+		assert_eq!(layout.filepath("TABLE", None, &table).unwrap(), ["public", "TABLES", "t.sql"]);
+		assert_eq!(layout.filepath("TRIGGER", Some("view"), &table).unwrap(), ["public", "TABLES", "t.sql"]);
+		assert_eq!(layout.filepath("INDEX", Some("materialized_view"), &table).unwrap(), ["public", "TABLES", "t.sql"]);
+
+		let schema = vars(&[("schema", ""), ("name", "public")]);
+		assert!(layout.filepath("SCHEMA", Some("public"), &schema).unwrap().is_empty());
+		assert_eq!(layout.filepath("SCHEMA", None, &schema).unwrap(), ["SCHEMAS", "public.sql"]);
+	}
+
+	#[test]
+	fn parse_templates() {
+		let layout = layout(r#"
+			[paths]
+			"TABLE" = "{schema}/tables/t_{name}.sql"
+			"EXTENSION" = "{schema}/extensions.sql"
+		"#).unwrap();
+		let table = vars(&[("schema", "s"), ("name", "a/b")]);
+		assert_eq!(layout.filepath("TABLE", None, &table).unwrap(), ["s", "tables", "t_a%2Fb.sql"]);
+		// Global objects have an empty schema, which leaves the component out.
+		let extension = vars(&[("schema", ""), ("name", "e")]);
+		assert_eq!(layout.filepath("EXTENSION", None, &extension).unwrap(), ["extensions.sql"]);
+		assert!(layout.filepath("TABLE", None, &vars(&[("schema", "s")])).unwrap_err().contains("variable {name} is not available"));
+	}
+
+	#[test]
+	fn invalid_templates() {
+		for (template, error) in [
+			("{schema}/{bogus}.sql", "unknown variable {bogus}"),
+			("{schema}/{name.sql", "unterminated variable reference"),
+			("{schema}/name}.sql", "unmatched \"}\""),
+			("{schema}//{name}.sql", "invalid path component \"\""),
+			("../{name}.sql", "invalid path component \"..\""),
+			("index.sql", "index.sql is reserved"),
+		] {
+			let err = layout(&format!("[paths]\n\"TABLE\" = {:?}\n", template)).unwrap_err();
+			assert!(err.contains(error), "{:?}: {}", template, err);
+		}
+	}
+
+	#[test]
+	fn invalid_keys() {
+		for (key, error) in [
+			("TABLES", "unknown desc \"TABLES\""),
+			("table", "unknown desc \"table\""),
+			("COMMENT", "COMMENT entries go with the object they belong to"),
+			("TABLE:view", "TABLE can't be qualified with a kind"),
+			("TRIGGER:sequence", "unknown kind \"sequence\" for TRIGGER"),
+			("FUNCTION:", "unknown kind \"\" for FUNCTION"),
+		] {
+			let err = layout(&format!("[paths]\n{:?} = \"x.sql\"\n", key)).unwrap_err();
+			assert!(err.contains(error), "{:?}: {}", key, err);
+		}
+		assert!(layout("[tables]\n").is_err());
+		assert!(layout("[paths]\n\"TABLE\" = 1\n").is_err());
+	}
+
+	#[test]
+	fn overrides() {
+		let layout = layout(r#"
+			[paths]
+			"INDEX" = "{schema}/INDEXES/{name}.sql"
+			"TRIGGER:view" = "{schema}/VIEWS/{table}.sql"
+			"FUNCTION" = "{schema}/functions/{function}.sql"
+			"FK CONSTRAINT" = "{schema}/TABLES/{table}.sql"
+		"#).unwrap();
+		let index = vars(&[("schema", "s"), ("name", "i"), ("table", "t")]);
+		assert_eq!(layout.filepath("INDEX", None, &index).unwrap(), ["s", "INDEXES", "i.sql"]);
+		assert_eq!(layout.filepath("FK CONSTRAINT", Some("table"), &index).unwrap(), ["s", "TABLES", "t.sql"]);
+		// A kind-specific entry is preferred, but only for that kind.
+		assert_eq!(layout.filepath("TRIGGER", Some("view"), &index).unwrap(), ["s", "VIEWS", "t.sql"]);
+		assert_eq!(layout.filepath("TRIGGER", Some("table"), &index).unwrap(), ["s", "TABLES", "t.sql"]);
+		// Setting FUNCTION gets rid of the default FUNCTION:trigger entry.
+		let function = vars(&[("schema", "s"), ("function", "f")]);
+		assert_eq!(layout.filepath("FUNCTION", Some("trigger"), &function).unwrap(), ["s", "functions", "f.sql"]);
+		// Anything not mentioned stays as it was.
+		let table = vars(&[("schema", "s"), ("name", "t")]);
+		assert_eq!(layout.filepath("TABLE", None, &table).unwrap(), ["s", "TABLES", "t.sql"]);
+	}
+
+	#[test]
+	fn empty_template_leaves_objects_out() {
+		let layout = layout("[paths]\n\"POLICY\" = \"\"\n").unwrap();
+		let policy = vars(&[("schema", "s"), ("table", "t")]);
+		assert!(layout.filepath("POLICY", None, &policy).unwrap().is_empty());
+	}
+}
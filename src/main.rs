@@ -3,20 +3,35 @@
 use std::ffi::OsString;
 use std::env;
 use std::fs::{self, File};
-use std::io::Write;
+use std::io::{BufWriter, IsTerminal, Write};
 use std::path::Path;
 use std::process;
+use std::time::Duration;
 
 use getopts::Options;
 
 mod auxiliary_data;
 mod custom_dump_reader;
+mod filter;
+mod identifiers;
+mod layout;
 mod postgres_configuration;
+mod progress;
+mod pg_dump_discovery;
+mod pg_dump_options;
 mod pg_dump_subprocess;
 mod output;
+mod path_encoding;
+mod role_map;
+mod tls;
 
-use custom_dump_reader::SplitDumpDirectory;
+use custom_dump_reader::{SplitDumpDirectory, SplitOptions, known_descs};
+use filter::{NamePattern, ObjectFilter};
+use layout::Layout;
 use output::*;
+use path_encoding::is_safe_path_component;
+use progress::{Phase, Progress, ProgressFormat};
+use role_map::RoleMap;
 
 fn print_usage(mut stream: impl std::io::Write, program: &str) {
 	let brief = format!("pg_split_dump takes a schema-only dump into a directory format
@@ -26,22 +41,110 @@ Usage:
 
 Options:
   --pg-dump-binary=PG_DUMP_PATH
-                      use the pg_dump binary in PG_DUMP_PATH
-  --format=d|t
-                      output file format: directory or tar archive; the default
-                      is a directory unless OUTPUT ends in \".tar\"
+                      use the pg_dump binary in PG_DUMP_PATH; by default the
+                      newest pg_dump found in PATH, in pg_config --bindir or
+                      in /usr/lib/postgresql/*/bin is used.  It must not be
+                      older than the server.
+  --pg-dump-arg=OPTION
+                      pass OPTION on to pg_dump, e.g.
+                      --pg-dump-arg=--lock-wait-timeout=10s; can be given more
+                      than once.  Options which would interfere with
+                      pg_split_dump (e.g. --format or --data-only) are
+                      rejected.
+  --pg-dump-timeout=SECONDS
+                      give up and kill pg_dump if it hasn't finished in
+                      SECONDS seconds
+  --format=d|t|g|s|z
+                      output file format: directory, tar archive, gzip
+                      compressed tar archive, zstd compressed tar archive or
+                      zip archive; the default is a directory unless OUTPUT
+                      ends in \".tar\", \".tar.gz\", \".tgz\", \".tar.zst\" or
+                      \".zip\".  If OUTPUT is \"-\", a tar archive is written
+                      to stdout.
+  --compression-level=LEVEL
+                      compress the tar archive at LEVEL: 0 to 9 for gzip (the
+                      default is 6) and 1 to 22 for zstd (the default is 3)
+  --fail-on-path-collisions
+                      fail if two objects' file names only differ in case or
+                      Unicode normalization; by default the one coming later
+                      in the dump gets a hash appended to its name
+  -n, --schema=PATTERN
+                      only include objects in schemas matching PATTERN; can be
+                      given more than once.  Patterns work like in pg_dump.
+  -N, --exclude-schema=PATTERN
+                      leave out objects in schemas matching PATTERN
+  -t, --table=PATTERN
+                      only include tables, views and sequences matching
+                      PATTERN, and the objects belonging to them
+  -T, --exclude-table=PATTERN
+                      leave out tables, views and sequences matching PATTERN
+  --only-kind=KIND[,KIND]..
+                      only include objects of the given kinds, e.g. FUNCTION
+                      or MATERIALIZED_VIEW (see pg_restore --list for the
+                      names); comments and privileges are included along with
+                      their objects
+  --exclude-kind=KIND[,KIND]..
+                      leave out objects of the given kinds, along with their
+                      comments and privileges
+  --exclude-extension-members
+                      leave out objects which belong to an extension (such as
+                      privileges granted on them), and anything in schemas
+                      created by an extension; only the CREATE EXTENSION
+                      statement is kept
+  --exclude-extension-schema=EXTENSION
+                      leave out the schemas EXTENSION created (as opposed to
+                      the one it was installed into), and everything in
+                      them; can be given more than once
+  --layout=LAYOUT_FILE
+                      read the directory layout from LAYOUT_FILE; see
+                      src/default_layout.toml for the format and the default
+  --no-acl
+                      don't write privileges (GRANT and REVOKE statements
+                      and default privileges)
+  --no-comments
+                      don't write comments
+  --no-owner
+                      don't write ALTER .. OWNER TO statements
+  --no-security-labels
+                      don't write security labels
+  --role-map=ROLE_MAP_FILE
+                      rename roles in owners, privileges, policies and user
+                      mappings as specified in ROLE_MAP_FILE, a TOML file
+                      with a [roles] table mapping old names to new ones
+  --split-function-overloads
+                      write each overload of a function into a separate file
+                      named after its signature, e.g.
+                      \"foo(integer,character_varying).sql\"
+  --strict
+                      fail if the dump contains objects of an unknown type;
+                      by default they are written into UNCLASSIFIED/ and
+                      the exit status is 2
+  -W, --password
+                      prompt for the database password before connecting; it
+                      is passed on to pg_dump through its environment
+  --progress[=text|json]
+                      report progress on stderr: a status line if stderr is a
+                      terminal, or with --progress=json, one JSON object per
+                      line, e.g. {{\"phase\":\"reading_toc\",\"done\":10,
+                      \"total\":200,\"elapsed_ms\":1500}}
 
 ", program);
 	stream.write_all(brief.as_bytes()).unwrap();
 }
 
+#[allow(clippy::redundant_static_lifetimes)]
 const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
+// Exit status for when the output was written, but some objects ended up in
+// UNCLASSIFIED/.
+const EXIT_PARTIAL: i32 = 2;
+
 fn print_version() {
 	println!("pg_split_dump version {}", VERSION)
 }
 
-fn write_split_directory_contents(dir_path: &Path, contents: &SplitDumpDirectory, create: bool) {
+#[allow(clippy::write_with_newline)]
+fn write_split_directory_contents(dir_path: &Path, contents: &SplitDumpDirectory, create: bool, progress: &mut Progress) {
 	if create {
 		if let Err(err) = fs::create_dir(dir_path) {
 			eprintln!("could not create output subdirectory {}: {}", dir_path.display(), err);
@@ -50,6 +153,12 @@ fn write_split_directory_contents(dir_path: &Path, contents: &SplitDumpDirectory
 	}
 
 	for (filename, file_contents) in &contents.files {
+		// The names should have been escaped already; this is just to be sure
+		// nothing is ever written outside the output directory.
+		if !is_safe_path_component(filename) {
+			eprintln!("refusing to create output file with unsafe name {:?} in {}", filename, dir_path.display());
+			process::exit(1);
+		}
 		let path = dir_path.join(filename);
 
 		let mut file = match File::create(&path) {
@@ -69,14 +178,54 @@ fn write_split_directory_contents(dir_path: &Path, contents: &SplitDumpDirectory
 			eprintln!("could not write to output file {}: {}", path.display(), err);
 			process::exit(1);
 		}
+		progress.advance();
 	}
 
 	for (subdir, subdir_contents) in &contents.dirs {
+		if !is_safe_path_component(subdir) {
+			eprintln!("refusing to create output subdirectory with unsafe name {:?} in {}", subdir, dir_path.display());
+			process::exit(1);
+		}
 		let subdir_path = dir_path.join(subdir);
-		write_split_directory_contents(&subdir_path, subdir_contents, true);
+		write_split_directory_contents(&subdir_path, subdir_contents, true, progress);
+	}
+}
+
+// Writes the split dump into writer as a tar archive, compressed as requested,
+// and returns the writer.
+fn write_tar_archive<W: Write>(writer: W, compression: Compression, output_name: &str, contents: &SplitDumpDirectory, progress: &mut Progress) -> W {
+	let result = CompressingWriter::new(writer, compression)
+		.map_err(ArchiveOutputError::from)
+		.and_then(|writer| {
+			let mut writer = TarOutputWriter::new(writer);
+			writer.write_from_split_dump(contents, progress)?;
+			Ok(writer.finish()?.finish()?)
+		});
+	match result {
+		Err(err) => {
+			progress.end_line();
+			eprintln!("could not write output archive {}: {}", output_name, err);
+			process::exit(1);
+		},
+		Ok(writer) => writer,
+	}
+}
+
+// Writes the split dump into file as a zip archive, and returns the file.
+fn write_zip_archive(file: File, output_name: &str, contents: &SplitDumpDirectory, progress: &mut Progress) -> File {
+	let mut writer = ZipOutputWriter::new(file);
+	let result = writer.write_from_split_dump(contents, progress).and_then(|_| writer.finish());
+	match result {
+		Err(err) => {
+			progress.end_line();
+			eprintln!("could not write output archive {}: {}", output_name, err);
+			process::exit(1);
+		},
+		Ok(file) => file,
 	}
 }
 
+#[allow(clippy::len_zero, clippy::needless_borrows_for_generic_args, clippy::to_string_in_format_args)]
 fn main() -> std::io::Result<()> {
 	let args: Vec<String> = env::args().collect();
 	let program = args[0].clone();
@@ -85,7 +234,29 @@ fn main() -> std::io::Result<()> {
 	opts.optflag("h", "help", "print this help menu");
 	opts.optflag("v", "version", "print version and exit");
 	opts.optopt("", "pg-dump-binary", "use the pg_dump binary in PG_DUMP_PATH", "PG_DUMP_PATH");
+	opts.optmulti("", "pg-dump-arg", "pass OPTION on to pg_dump", "OPTION");
+	opts.optopt("", "pg-dump-timeout", "kill pg_dump if it takes longer than SECONDS", "SECONDS");
 	opts.optopt("", "format", "output format", "FORMAT");
+	opts.optopt("", "compression-level", "compress the tar archive at LEVEL", "LEVEL");
+	opts.optflag("", "fail-on-path-collisions", "fail on file names which only differ in case");
+	opts.optmulti("n", "schema", "only include schemas matching PATTERN", "PATTERN");
+	opts.optmulti("N", "exclude-schema", "leave out schemas matching PATTERN", "PATTERN");
+	opts.optmulti("t", "table", "only include tables matching PATTERN", "PATTERN");
+	opts.optmulti("T", "exclude-table", "leave out tables matching PATTERN", "PATTERN");
+	opts.optmulti("", "only-kind", "only include objects of the given kinds", "KIND[,KIND]..");
+	opts.optmulti("", "exclude-kind", "leave out objects of the given kinds", "KIND[,KIND]..");
+	opts.optflag("", "exclude-extension-members", "leave out objects belonging to extensions");
+	opts.optmulti("", "exclude-extension-schema", "leave out the schemas EXTENSION created", "EXTENSION");
+	opts.optopt("", "layout", "read the directory layout from LAYOUT_FILE", "LAYOUT_FILE");
+	opts.optflag("", "no-acl", "don't write privileges");
+	opts.optflag("", "no-comments", "don't write comments");
+	opts.optflag("", "no-security-labels", "don't write security labels");
+	opts.optflag("", "no-owner", "don't write ALTER .. OWNER TO statements");
+	opts.optopt("", "role-map", "rename roles as specified in ROLE_MAP_FILE", "ROLE_MAP_FILE");
+	opts.optflag("", "split-function-overloads", "one file per function signature");
+	opts.optflag("", "strict", "fail on objects of an unknown type");
+	opts.optflag("W", "password", "prompt for a password");
+	opts.optflagopt("", "progress", "report progress on stderr", "FORMAT");
 
 	let mut matches = match opts.parse(&args[1..]) {
 		Err(f) => {
@@ -102,11 +273,118 @@ fn main() -> std::io::Result<()> {
 		print_version();
 		process::exit(0);
 	}
-	let pg_dump_binary = match matches.opt_str("pg-dump-binary") {
-		Some(pg_dump_binary) => pg_dump_binary,
-		None => panic!("pg-dump-binary is currently required"),
+	let pg_dump_binary = matches.opt_str("pg-dump-binary").map(OsString::from);
+	let pg_dump_timeout = match matches.opt_str("pg-dump-timeout") {
+		Some(timeout) => match timeout.parse::<u64>() {
+			Ok(seconds) if seconds > 0 => Some(Duration::from_secs(seconds)),
+			_ => {
+				eprintln!("invalid --pg-dump-timeout value {:?}", timeout);
+				process::exit(1);
+			},
+		},
+		None => None,
+	};
+	let extra_pg_dump_args = matches.opt_strs("pg-dump-arg");
+	for arg in &extra_pg_dump_args {
+		if let Err(err) = pg_dump_options::validate(arg) {
+			eprintln!("invalid --pg-dump-arg: {}", err);
+			process::exit(1);
+		}
+	}
+
+	let layout = match matches.opt_str("layout") {
+		Some(layout_path) => match Layout::load(Path::new(&layout_path)) {
+			Err(err) => {
+				eprintln!("{}", err);
+				process::exit(1);
+			},
+			Ok(layout) => layout,
+		},
+		None => Layout::default(),
+	};
+
+	let role_map = match matches.opt_str("role-map") {
+		Some(role_map_path) => match RoleMap::load(Path::new(&role_map_path)) {
+			Err(err) => {
+				eprintln!("{}", err);
+				process::exit(1);
+			},
+			Ok(role_map) => role_map,
+		},
+		None => RoleMap::default(),
+	};
+
+	let parse_patterns = |option: &str, qualified: bool| -> Vec<NamePattern> {
+		let mut patterns = vec![];
+		for pattern in matches.opt_strs(option) {
+			match NamePattern::parse(&pattern, qualified) {
+				Err(err) => {
+					eprintln!("invalid --{} pattern: {}", option, err);
+					process::exit(1);
+				},
+				Ok(pattern) => patterns.push(pattern),
+			}
+		}
+		patterns
+	};
+	// Kinds are TOC entry descs.  Allow underscores instead of spaces so that
+	// they don't need quoting, and lower case letters.
+	let parse_kinds = |option: &str| -> Vec<String> {
+		let mut kinds = vec![];
+		for list in matches.opt_strs(option) {
+			for kind in list.split(',') {
+				let kind = kind.trim().replace('_', " ").to_uppercase();
+				if kind.is_empty() {
+					eprintln!("invalid --{} value {:?}", option, list);
+					process::exit(1);
+				}
+				if !known_descs().any(|desc| desc == kind) {
+					let valid_kinds: Vec<String> = known_descs().map(|desc| desc.replace(' ', "_")).collect();
+					eprintln!("unknown object kind {:?} in --{}; valid kinds are: {}", kind.replace(' ', "_"), option, valid_kinds.join(", "));
+					process::exit(1);
+				}
+				kinds.push(kind);
+			}
+		}
+		kinds
+	};
+	let filter = ObjectFilter{
+		include_schemas: parse_patterns("schema", false),
+		exclude_schemas: parse_patterns("exclude-schema", false),
+		include_tables: parse_patterns("table", true),
+		exclude_tables: parse_patterns("exclude-table", true),
+		only_kinds: parse_kinds("only-kind"),
+		exclude_kinds: parse_kinds("exclude-kind"),
+		exclude_extension_members: matches.opt_present("exclude-extension-members"),
+		exclude_extension_schemas: matches.opt_strs("exclude-extension-schema"),
+	};
+
+	let split_options = SplitOptions{
+		strict: matches.opt_present("strict"),
+		split_function_overloads: matches.opt_present("split-function-overloads"),
+		layout,
+		fail_on_path_collisions: matches.opt_present("fail-on-path-collisions"),
+		no_owner: matches.opt_present("no-owner"),
+		role_map,
+		no_acl: matches.opt_present("no-acl"),
+		no_comments: matches.opt_present("no-comments"),
+		no_security_labels: matches.opt_present("no-security-labels"),
+		filter,
+	};
+
+	let progress_format = if matches.opt_present("progress") {
+		let format = matches.opt_str("progress").unwrap_or("text".to_string());
+		match ProgressFormat::from_string(&format) {
+			None => {
+				eprintln!("invalid progress format {}", format);
+				process::exit(1);
+			},
+			Some(format) => Some(format),
+		}
+	} else {
+		None
 	};
-	let pg_dump_binary = OsString::from(pg_dump_binary);
+	let mut progress = Progress::new(progress_format);
 
 	let output_format = match matches.opt_str("format") {
 		Some(fmt) => {
@@ -132,25 +410,60 @@ fn main() -> std::io::Result<()> {
 		process::exit(1);
 	}
 
+	let write_to_stdout = output_path == "-";
 	let output_format = match output_format {
 		Some(fmt) => fmt,
-		None => if output_path.to_string_lossy().ends_with(".tar") {
+		None => if write_to_stdout {
 			OutputFormat::TarArchive
 		} else {
-			OutputFormat::Directory
+			OutputFormat::from_path(&output_path.to_string_lossy())
 		},
 	};
 
+	let compression_level = match matches.opt_str("compression-level") {
+		None => None,
+		Some(level) => match level.parse::<i32>() {
+			Err(_) => {
+				eprintln!("invalid compression level {}", level);
+				process::exit(1);
+			},
+			Ok(level) => Some(level),
+		},
+	};
+	let compression = match output_format.compression(compression_level) {
+		Err(err) => {
+			eprintln!("{}", err);
+			process::exit(1);
+		},
+		Ok(compression) => compression,
+	};
+
 	let output_path = Path::new(&output_path);
-	if output_path.exists() {
+	if write_to_stdout {
+		if !output_format.is_tar_archive() {
+			eprintln!("only tar archives can be written to stdout");
+			process::exit(1);
+		}
+		if std::io::stdout().is_terminal() {
+			eprintln!("refusing to write a tar archive to a terminal");
+			process::exit(1);
+		}
+	} else if output_path.exists() {
 		eprintln!("output {} already exists", output_path.display());
 		process::exit(1);
 	}
 
-	let pg_config = postgres_configuration::create(&conninfo);
+	// Resolving the configuration might prompt for a password or print
+	// warnings, so the status line only starts after it.
+	let conn_config = postgres_configuration::create(&conninfo, matches.opt_present("password"));
+	progress.phase(Phase::Connecting);
 
-	let pg_conn = pg_config.connect(postgres::NoTls);
+	let pg_conn = match &conn_config.tls_connector {
+		None => conn_config.pg_config.connect(postgres::NoTls),
+		Some(tls_connector) => conn_config.pg_config.connect(tls_connector.clone()),
+	};
 	if let Err(e) = pg_conn {
+		progress.end_line();
 		eprintln!("could not connect to postgres: {}", e);
 		process::exit(1);
 	}
@@ -158,12 +471,35 @@ fn main() -> std::io::Result<()> {
 
 	let res = pg_conn.execute("SET default_transaction_read_only TO TRUE", &[]);
 	if let Err(e) = res {
+		progress.end_line();
 		eprintln!("could not set default_transaction_read_only: {}", e);
 		process::exit(1);
 	}
 
+	let server_version_num = match pg_conn.query_one("SHOW server_version_num", &[]) {
+		Err(e) => {
+			progress.end_line();
+			eprintln!("could not query the server version: {}", e);
+			process::exit(1);
+		},
+		Ok(row) => row.get::<_, String>(0).parse::<i32>().unwrap(),
+	};
+	let pg_dump_binary = match pg_dump_binary {
+		Some(path) => pg_dump_discovery::check(&path, server_version_num),
+		None => pg_dump_discovery::find(server_version_num, &mut progress),
+	};
+	let pg_dump_binary = match pg_dump_binary {
+		Err(err) => {
+			progress.end_line();
+			eprintln!("{}", err);
+			process::exit(1);
+		},
+		Ok(pg_dump_binary) => pg_dump_binary,
+	};
+
 	let mut txn = match pg_conn.transaction() {
 		Err(e) => {
+			progress.end_line();
 			eprintln!("could not begin a database transaction: {}", e);
 			process::exit(1);
 		},
@@ -172,6 +508,7 @@ fn main() -> std::io::Result<()> {
 
 	let row = match txn.query_one("SELECT pg_export_snapshot()", &[]) {
 		Err(e) => {
+			progress.end_line();
 			eprintln!("could not export a database snapshot: {}", e);
 			process::exit(1);
 		},
@@ -179,25 +516,63 @@ fn main() -> std::io::Result<()> {
 	};
 	let snapshot_id: String = row.get(0);
 
-	let pg_dump = match pg_dump_subprocess::PgDumpSubprocess::new(&pg_dump_binary, &conninfo, &snapshot_id) {
-		Err(_err) => {
-			//eprintln!("could not start pg_dump subprocess: {}", err);
-			process::exit(1);
-		},
-		Ok(pg_dump) => pg_dump,
-	};
-
+	// This could run alongside pg_dump, but the filter has to be checked
+	// against the catalog before there's any point in starting it.
 	let aux_data = match auxiliary_data::query(&mut txn) {
 		Err(err) => {
+			progress.end_line();
 			eprintln!("{}", err);
 			process::exit(1);
 		},
 		Ok(aux_data) => aux_data,
 	};
+	if let Err(err) = split_options.filter.excluded_extension_schemas(&aux_data) {
+		progress.end_line();
+		eprintln!("{}", err);
+		process::exit(1);
+	}
 
-	let dump = match custom_dump_reader::read_dump(pg_dump, &aux_data) {
+	let mut pg_dump_args = split_options.filter.pg_dump_args();
+	if split_options.no_acl {
+		pg_dump_args.push("--no-acl".to_string());
+	}
+	if split_options.no_comments {
+		pg_dump_args.push("--no-comments".to_string());
+	}
+	if split_options.no_security_labels {
+		pg_dump_args.push("--no-security-labels".to_string());
+	}
+	pg_dump_args.extend(extra_pg_dump_args);
+
+	let mut pg_dump = match pg_dump_subprocess::PgDumpSubprocess::new(
+		pg_dump_binary.path.as_os_str(),
+		&conn_config.pg_dump_conninfo,
+		conn_config.pg_dump_password.as_deref(),
+		&snapshot_id,
+		&pg_dump_args,
+		pg_dump_timeout,
+	) {
+		Err(err) => {
+			progress.end_line();
+			eprintln!("{}", err);
+			process::exit(err.exit_status());
+		},
+		Ok(pg_dump) => pg_dump,
+	};
+	progress.phase(Phase::RunningPgDump);
+
+	let dump = custom_dump_reader::read_dump(&mut pg_dump, &aux_data, &split_options, &mut progress);
+	progress.end_line();
+	// If pg_dump failed, that's what went wrong, even if it also made the dump
+	// unreadable.
+	if let Err(err) = pg_dump.finish() {
+		eprintln!("{}", err);
+		process::exit(err.exit_status());
+	}
+	let dump = match dump {
 		Err(err) => {
-			panic!("{:?}", err);
+			eprintln!("could not split the dump: {}", err);
+			process::exit(1);
 		},
 		Ok(dump) => dump,
 	};
@@ -207,30 +582,41 @@ fn main() -> std::io::Result<()> {
 		process::exit(1);
 	}
 
+	progress.counted_phase(Phase::WritingFiles, dump.split_root.file_count());
 	if output_format == OutputFormat::Directory {
 		if let Err(err) = fs::create_dir(&output_path) {
 			eprintln!("could not create output directory: {}", err);
 			process::exit(1);
 		}
 
-		write_split_directory_contents(&output_path, &dump.split_root, false);
-	} else if output_format == OutputFormat::TarArchive {
-		let mut writer = match TarOutputWriter::new(output_path) {
+		write_split_directory_contents(output_path, &dump.split_root, false, &mut progress);
+	} else if write_to_stdout {
+		let stdout = BufWriter::new(std::io::stdout().lock());
+		write_tar_archive(stdout, compression, "to stdout", &dump.split_root, &mut progress);
+	} else {
+		let file = match File::create(output_path) {
 			Err(err) => {
-				eprintln!("could not start writing to output archive: {:?}", err);
+				eprintln!("could not create output file {}: {}", output_path.display(), err);
 				process::exit(1);
 			},
-			Ok(writer) => writer,
+			Ok(file) => file,
 		};
-
-		if let Err(err) = writer.write_from_split_dump(&dump.split_root) {
-			eprintln!("could not start writing to output archive: {:?}", err);
+		let output_name = output_path.display().to_string();
+		let file = if output_format == OutputFormat::ZipArchive {
+			write_zip_archive(file, &output_name, &dump.split_root, &mut progress)
+		} else {
+			write_tar_archive(file, compression, &output_name, &dump.split_root, &mut progress)
+		};
+		if let Err(err) = file.sync_all() {
+			eprintln!("could not write output file {}: {}", output_name, err);
 			process::exit(1);
 		}
+	}
+	progress.phase(Phase::Done);
 
-		writer.sync();
-	} else {
-		panic!("{:?}", output_format);
+	if dump.unclassified_items > 0 {
+		progress.warn(&format!("{} objects could not be classified", dump.unclassified_items));
+		process::exit(EXIT_PARTIAL);
 	}
 
 	Ok(())
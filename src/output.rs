@@ -1,14 +1,18 @@
-use std::io;
+use std::io::{self, Seek, Write};
 use std::fmt;
-use std::fs::{self, File};
 use std::path::Path;
-use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::custom_dump_reader::SplitDumpDirectory;
+use crate::path_encoding::is_safe_path_component;
+use crate::progress::Progress;
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum OutputFormat {
 	TarArchive,
+	GzipTarArchive,
+	ZstdTarArchive,
+	ZipArchive,
 	Directory,
 }
 
@@ -16,99 +20,440 @@ impl OutputFormat {
 	pub fn from_string(s: &str) -> Option<OutputFormat> {
 		match s {
 			"t" => Some(OutputFormat::TarArchive),
+			"g" => Some(OutputFormat::GzipTarArchive),
+			"s" => Some(OutputFormat::ZstdTarArchive),
+			"z" => Some(OutputFormat::ZipArchive),
 			"d" => Some(OutputFormat::Directory),
 			_ => None,
 		}
 	}
+
+	// Infers the format from the suffix of the output path.
+	pub fn from_path(path: &str) -> OutputFormat {
+		if path.ends_with(".tar") {
+			return OutputFormat::TarArchive;
+		} else if path.ends_with(".tar.gz") || path.ends_with(".tgz") {
+			return OutputFormat::GzipTarArchive;
+		} else if path.ends_with(".tar.zst") {
+			return OutputFormat::ZstdTarArchive;
+		} else if path.ends_with(".zip") {
+			return OutputFormat::ZipArchive;
+		}
+		OutputFormat::Directory
+	}
+
+	pub fn is_tar_archive(&self) -> bool {
+		match self {
+			OutputFormat::TarArchive | OutputFormat::GzipTarArchive | OutputFormat::ZstdTarArchive => true,
+			OutputFormat::ZipArchive | OutputFormat::Directory => false,
+		}
+	}
+
+	// Checks the --compression-level given for this format, and returns the
+	// compression to use.
+	pub fn compression(&self, level: Option<i32>) -> Result<Compression, String> {
+		match self {
+			OutputFormat::GzipTarArchive => {
+				let level = level.unwrap_or(6);
+				if !(0..=9).contains(&level) {
+					return Err(format!("gzip compression level must be between 0 and 9, not {}", level));
+				}
+				Ok(Compression::Gzip(level as u32))
+			},
+			OutputFormat::ZstdTarArchive => {
+				let level = level.unwrap_or(zstd::DEFAULT_COMPRESSION_LEVEL);
+				let max_level = *zstd::compression_level_range().end();
+				if !(1..=max_level).contains(&level) {
+					return Err(format!("zstd compression level must be between 1 and {}, not {}", max_level, level));
+				}
+				Ok(Compression::Zstd(level))
+			},
+			_ if level.is_some() => Err("--compression-level can only be used with compressed tar archives".to_string()),
+			_ => Ok(Compression::None),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+	None,
+	Gzip(u32),
+	Zstd(i32),
+}
+
+// Compresses everything written into it, if asked to, before passing it on.
+pub enum CompressingWriter<W: Write> {
+	Uncompressed(W),
+	Gzip(flate2::write::GzEncoder<W>),
+	Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> CompressingWriter<W> {
+	pub fn new(writer: W, compression: Compression) -> io::Result<CompressingWriter<W>> {
+		Ok(match compression {
+			Compression::None => CompressingWriter::Uncompressed(writer),
+			Compression::Gzip(level) => CompressingWriter::Gzip(
+				flate2::write::GzEncoder::new(writer, flate2::Compression::new(level)),
+			),
+			Compression::Zstd(level) => CompressingWriter::Zstd(zstd::Encoder::new(writer, level)?),
+		})
+	}
+
+	// Writes out the end of the compressed stream, and returns the underlying
+	// writer.
+	pub fn finish(self) -> io::Result<W> {
+		let mut writer = match self {
+			CompressingWriter::Uncompressed(writer) => writer,
+			CompressingWriter::Gzip(encoder) => encoder.finish()?,
+			CompressingWriter::Zstd(encoder) => encoder.finish()?,
+		};
+		writer.flush()?;
+		Ok(writer)
+	}
+}
+
+impl<W: Write> Write for CompressingWriter<W> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		match self {
+			CompressingWriter::Uncompressed(writer) => writer.write(buf),
+			CompressingWriter::Gzip(encoder) => encoder.write(buf),
+			CompressingWriter::Zstd(encoder) => encoder.write(buf),
+		}
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		match self {
+			CompressingWriter::Uncompressed(writer) => writer.flush(),
+			CompressingWriter::Gzip(encoder) => encoder.flush(),
+			CompressingWriter::Zstd(encoder) => encoder.flush(),
+		}
+	}
 }
 
+// Named like DumpReadError's and PgDumpError's variants.
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
-pub enum TarOutputError {
+pub enum ArchiveOutputError {
 	IOError(io::Error),
+	ZipError(zip::result::ZipError),
 	OtherError(String),
 }
 
-impl From<io::Error> for TarOutputError {
+impl From<io::Error> for ArchiveOutputError {
 	fn from(error: io::Error) -> Self {
 	    Self::IOError(error)
 	}
 }
 
-impl fmt::Display for TarOutputError {
+impl From<zip::result::ZipError> for ArchiveOutputError {
+	fn from(error: zip::result::ZipError) -> Self {
+	    Self::ZipError(error)
+	}
+}
+
+impl fmt::Display for ArchiveOutputError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		match self {
-			TarOutputError::IOError(err) => write!(f, "IOError: {}", err),
-			TarOutputError::OtherError(err) => write!(f, "{}", err),
+			ArchiveOutputError::IOError(err) => write!(f, "IOError: {}", err),
+			ArchiveOutputError::ZipError(err) => write!(f, "ZipError: {}", err),
+			ArchiveOutputError::OtherError(err) => write!(f, "{}", err),
 		}
 	}
 }
 
-pub struct TarOutputWriter {
-	output_filename: String,
-	metadata: fs::Metadata,
-	archive: tar::Builder<File>,
+// Calls write_file for every file in the split dump with its path inside the
+// archive and its contents.  Files come before subdirectories, and both are
+// visited in order of their names so that the archive comes out the same way
+// every time.
+fn walk_split_dump<F>(path: &str, contents: &SplitDumpDirectory, write_file: &mut F, progress: &mut Progress) -> Result<(), ArchiveOutputError>
+where
+	F: FnMut(&str, &str) -> Result<(), ArchiveOutputError>,
+{
+	let mut filenames: Vec<&String> = contents.files.keys().collect();
+	filenames.sort();
+	for filename in filenames {
+		if !is_safe_path_component(filename) {
+			return Err(ArchiveOutputError::OtherError(format!("unsafe file name {:?}", filename)));
+		}
+		let materialized = contents.files[filename].join("\n") + "\n";
+		write_file(&(path.to_string() + filename), &materialized)?;
+		progress.advance();
+	}
+	let mut subdirs: Vec<&String> = contents.dirs.keys().collect();
+	subdirs.sort();
+	for subdir in subdirs {
+		if !is_safe_path_component(subdir) {
+			return Err(ArchiveOutputError::OtherError(format!("unsafe directory name {:?}", subdir)));
+		}
+		let subdir_path = path.to_string() + subdir + "/";
+		walk_split_dump(&subdir_path, &contents.dirs[subdir], write_file, progress)?;
+	}
+	Ok(())
 }
 
-impl TarOutputWriter {
-	pub fn new(output_path: &Path) -> Result<TarOutputWriter, TarOutputError> {
-		let file = File::create(&output_path)?;
-		let metadata = match file.metadata() {
-			Err(err) => {
-				let err = TarOutputError::OtherError(
-					format!("could not get metadata for {}: {}", &output_path.display(), err),
-				);
-				return Err(err);
-			},
-			Ok(metadata) => metadata,
+// Writes the split dump as a tar archive into any writer, e.g. a file or
+// stdout.  Since there's no file to take the metadata from, every entry gets
+// the same made-up metadata: mode 0644, owned by us, modified now.
+pub struct TarOutputWriter<W: Write> {
+	archive: tar::Builder<W>,
+	mtime: u64,
+	uid: u64,
+	gid: u64,
+}
+
+#[cfg(unix)]
+fn current_uid_gid() -> (u64, u64) {
+	unsafe { (libc::getuid() as u64, libc::getgid() as u64) }
+}
+
+#[cfg(not(unix))]
+fn current_uid_gid() -> (u64, u64) {
+	(0, 0)
+}
+
+impl<W: Write> TarOutputWriter<W> {
+	pub fn new(writer: W) -> TarOutputWriter<W> {
+		let mtime = match SystemTime::now().duration_since(UNIX_EPOCH) {
+			Err(_) => 0,
+			Ok(duration) => duration.as_secs(),
 		};
-		let archive = tar::Builder::new(file);
+		let (uid, gid) = current_uid_gid();
 
-		Ok(TarOutputWriter{
-			output_filename: output_path.to_string_lossy().into_owned(),
-			metadata: metadata,
-			archive: archive,
-		})
+		TarOutputWriter{
+			archive: tar::Builder::new(writer),
+			mtime,
+			uid,
+			gid,
+		}
 	}
 
-	pub fn write_from_split_dump(self: &mut TarOutputWriter, contents: &SplitDumpDirectory) -> Result<(), TarOutputError> {
-		self.write_directory(Path::new(""), contents)
+	pub fn write_from_split_dump(self: &mut TarOutputWriter<W>, contents: &SplitDumpDirectory, progress: &mut Progress) -> Result<(), ArchiveOutputError> {
+		walk_split_dump("", contents, &mut |path, contents| self.write_file(path, contents), progress)
 	}
 
-	fn write_file(self: &mut TarOutputWriter, parent: &Path, filename: &str, contents: &str) -> Result<(), TarOutputError> {
-		let path = parent.join(filename);
-
+	fn write_file(self: &mut TarOutputWriter<W>, path: &str, contents: &str) -> Result<(), ArchiveOutputError> {
 		let mut header = tar::Header::new_gnu();
-		header.set_metadata(&self.metadata);
+		header.set_entry_type(tar::EntryType::Regular);
+		header.set_mode(0o644);
+		header.set_uid(self.uid);
+		header.set_gid(self.gid);
+		header.set_mtime(self.mtime);
 		header.set_size(contents.len() as u64);
 		header.set_cksum();
 
-		self.archive.append_data(&mut header, &path, contents.as_bytes())?;
+		self.archive.append_data(&mut header, Path::new(path), contents.as_bytes())?;
 		Ok(())
 	}
 
-	fn write_directory(self: &mut TarOutputWriter, path: &Path, contents: &SplitDumpDirectory) -> Result<(), TarOutputError> {
-		for (filename, file_contents) in &contents.files {
-			let materialized = file_contents.join("\n") + "\n";
-			self.write_file(path, &filename, &materialized)?;
-		}
-		for (subdir, subdir_contents) in &contents.dirs {
-			let subdir_path = path.join(subdir);
-			self.write_directory(&subdir_path, subdir_contents)?
+	// Writes the end of the archive, and returns the underlying writer.
+	pub fn finish(self: TarOutputWriter<W>) -> Result<W, ArchiveOutputError> {
+		let mut writer = self.archive.into_inner()?;
+		writer.flush()?;
+		Ok(writer)
+	}
+}
+
+// Writes the split dump as a zip archive.  Unlike a tar archive, a zip
+// archive has its index at the end, so this needs a file it can seek in.
+// Every entry gets the same timestamp, the earliest one zip can represent
+// (1980-01-01 00:00:00), so that the same dump always produces the same
+// archive.
+pub struct ZipOutputWriter<W: Write + Seek> {
+	archive: zip::ZipWriter<W>,
+}
+
+impl<W: Write + Seek> ZipOutputWriter<W> {
+	pub fn new(writer: W) -> ZipOutputWriter<W> {
+		ZipOutputWriter{
+			archive: zip::ZipWriter::new(writer),
 		}
+	}
+
+	pub fn write_from_split_dump(self: &mut ZipOutputWriter<W>, contents: &SplitDumpDirectory, progress: &mut Progress) -> Result<(), ArchiveOutputError> {
+		walk_split_dump("", contents, &mut |path, contents| self.write_file(path, contents), progress)
+	}
+
+	fn write_file(self: &mut ZipOutputWriter<W>, path: &str, contents: &str) -> Result<(), ArchiveOutputError> {
+		let options = zip::write::SimpleFileOptions::default()
+			.compression_method(zip::CompressionMethod::Deflated)
+			.last_modified_time(zip::DateTime::default())
+			.unix_permissions(0o644);
+		self.archive.start_file(path, options)?;
+		self.archive.write_all(contents.as_bytes())?;
 		Ok(())
 	}
 
-	pub fn sync(self: TarOutputWriter) {
-		let file = match self.archive.into_inner() {
-			Err(err) => {
-				eprintln!("could not write output file {}: {}", self.output_filename, err);
-				process::exit(1);
-			},
-			Ok(file) => file,
-		};
-		if let Err(err) = file.sync_all() {
-			eprintln!("could not write output file {}: {}", self.output_filename, err);
-			process::exit(1);
+	// Writes the central directory, and returns the underlying writer.
+	pub fn finish(self: ZipOutputWriter<W>) -> Result<W, ArchiveOutputError> {
+		let mut writer = self.archive.finish()?;
+		writer.flush()?;
+		Ok(writer)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Read;
+
+	// A small split dump, with the names added in an order which is neither
+	// sorted nor likely to be HashMap order.
+	fn split_dump() -> SplitDumpDirectory {
+		let mut tables = SplitDumpDirectory::new();
+		tables.files.insert("users.sql".to_string(), vec!["CREATE TABLE users();".to_string()]);
+		tables.files.insert("accounts.sql".to_string(), vec!["CREATE TABLE accounts();".to_string(), String::new()]);
+		let mut public = SplitDumpDirectory::new();
+		public.dirs.insert("TABLES".to_string(), tables);
+		public.dirs.insert("FUNCTIONS".to_string(), SplitDumpDirectory::new());
+		let mut root = SplitDumpDirectory::new();
+		root.dirs.insert("public".to_string(), public);
+		root.files.insert("manifest.tsv".to_string(), vec!["path\tdesc\tschema\ttag".to_string()]);
+		root.files.insert("index.sql".to_string(), vec!["\\ir public/TABLES/accounts.sql".to_string()]);
+		root
+	}
+
+	#[test]
+	fn format_from_path() {
+		assert_eq!(OutputFormat::from_path("out.tar"), OutputFormat::TarArchive);
+		assert_eq!(OutputFormat::from_path("out.tar.gz"), OutputFormat::GzipTarArchive);
+		assert_eq!(OutputFormat::from_path("out.tgz"), OutputFormat::GzipTarArchive);
+		assert_eq!(OutputFormat::from_path("/backups/2024-01-01.tar.zst"), OutputFormat::ZstdTarArchive);
+		assert_eq!(OutputFormat::from_path("out.zip"), OutputFormat::ZipArchive);
+		assert_eq!(OutputFormat::from_path("out"), OutputFormat::Directory);
+		assert_eq!(OutputFormat::from_path("out.gz"), OutputFormat::Directory);
+		assert_eq!(OutputFormat::from_path("out.tar.bz2"), OutputFormat::Directory);
+		assert_eq!(OutputFormat::from_path("out.TAR"), OutputFormat::Directory);
+	}
+
+	#[test]
+	fn format_from_string() {
+		assert_eq!(OutputFormat::from_string("t"), Some(OutputFormat::TarArchive));
+		assert_eq!(OutputFormat::from_string("g"), Some(OutputFormat::GzipTarArchive));
+		assert_eq!(OutputFormat::from_string("s"), Some(OutputFormat::ZstdTarArchive));
+		assert_eq!(OutputFormat::from_string("z"), Some(OutputFormat::ZipArchive));
+		assert_eq!(OutputFormat::from_string("d"), Some(OutputFormat::Directory));
+		assert_eq!(OutputFormat::from_string("tar"), None);
+	}
+
+	#[test]
+	fn compression_levels() {
+		assert_eq!(OutputFormat::GzipTarArchive.compression(None), Ok(Compression::Gzip(6)));
+		assert_eq!(OutputFormat::GzipTarArchive.compression(Some(0)), Ok(Compression::Gzip(0)));
+		assert_eq!(OutputFormat::GzipTarArchive.compression(Some(9)), Ok(Compression::Gzip(9)));
+		assert_eq!(
+			OutputFormat::GzipTarArchive.compression(Some(10)),
+			Err("gzip compression level must be between 0 and 9, not 10".to_string()),
+		);
+		assert!(OutputFormat::GzipTarArchive.compression(Some(-1)).is_err());
+
+		let max_level = *zstd::compression_level_range().end();
+		assert_eq!(OutputFormat::ZstdTarArchive.compression(None), Ok(Compression::Zstd(zstd::DEFAULT_COMPRESSION_LEVEL)));
+		assert_eq!(OutputFormat::ZstdTarArchive.compression(Some(1)), Ok(Compression::Zstd(1)));
+		assert_eq!(OutputFormat::ZstdTarArchive.compression(Some(max_level)), Ok(Compression::Zstd(max_level)));
+		assert_eq!(
+			OutputFormat::ZstdTarArchive.compression(Some(0)),
+			Err(format!("zstd compression level must be between 1 and {}, not 0", max_level)),
+		);
+		assert!(OutputFormat::ZstdTarArchive.compression(Some(max_level + 1)).is_err());
+
+		for format in [OutputFormat::TarArchive, OutputFormat::ZipArchive, OutputFormat::Directory] {
+			assert_eq!(format.compression(None), Ok(Compression::None));
+			assert_eq!(
+				format.compression(Some(1)),
+				Err("--compression-level can only be used with compressed tar archives".to_string()),
+			);
+		}
+	}
+
+	#[test]
+	fn compressed_tar_archives() {
+		for compression in [Compression::Gzip(6), Compression::Zstd(3)] {
+			let mut writer = TarOutputWriter::new(CompressingWriter::new(vec![], compression).unwrap());
+			writer.write_from_split_dump(&split_dump(), &mut Progress::new(None)).unwrap();
+			let compressed = writer.finish().unwrap().finish().unwrap();
+
+			let decompressed: Box<dyn Read> = match compression {
+				Compression::Gzip(_) => Box::new(flate2::read::GzDecoder::new(compressed.as_slice())),
+				_ => Box::new(zstd::Decoder::new(compressed.as_slice()).unwrap()),
+			};
+			let paths: Vec<String> = tar::Archive::new(decompressed)
+				.entries()
+				.unwrap()
+				.map(|entry| entry.unwrap().path().unwrap().to_string_lossy().into_owned())
+				.collect();
+			assert_eq!(paths, ["index.sql", "manifest.tsv", "public/TABLES/accounts.sql", "public/TABLES/users.sql"]);
+		}
+	}
+
+	#[test]
+	fn tar_archive() {
+		let mut writer = TarOutputWriter::new(vec![]);
+		writer.write_from_split_dump(&split_dump(), &mut Progress::new(None)).unwrap();
+		let archive = writer.finish().unwrap();
+
+		let mut entries = vec![];
+		for entry in tar::Archive::new(archive.as_slice()).entries().unwrap() {
+			let mut entry = entry.unwrap();
+			let header = entry.header();
+			assert_eq!(header.entry_type(), tar::EntryType::Regular);
+			assert_eq!(header.mode().unwrap(), 0o644);
+			assert_eq!(header.uid().unwrap(), current_uid_gid().0);
+			assert!(header.mtime().unwrap() > 0);
+			let path = entry.path().unwrap().to_string_lossy().into_owned();
+			let mut contents = String::new();
+			entry.read_to_string(&mut contents).unwrap();
+			entries.push((path, contents));
+		}
+		assert_eq!(entries, vec![
+			("index.sql".to_string(), "\\ir public/TABLES/accounts.sql\n".to_string()),
+			("manifest.tsv".to_string(), "path\tdesc\tschema\ttag\n".to_string()),
+			("public/TABLES/accounts.sql".to_string(), "CREATE TABLE accounts();\n\n".to_string()),
+			("public/TABLES/users.sql".to_string(), "CREATE TABLE users();\n".to_string()),
+		]);
+	}
+
+	fn zip_archive(contents: &SplitDumpDirectory) -> Vec<u8> {
+		let mut writer = ZipOutputWriter::new(io::Cursor::new(vec![]));
+		writer.write_from_split_dump(contents, &mut Progress::new(None)).unwrap();
+		writer.finish().unwrap().into_inner()
+	}
+
+	#[test]
+	fn zip_archive_is_deterministic() {
+		let archive = zip_archive(&split_dump());
+		assert_eq!(zip_archive(&split_dump()), archive);
+
+		let mut zip = zip::ZipArchive::new(io::Cursor::new(archive)).unwrap();
+		let mut entries = vec![];
+		for i in 0..zip.len() {
+			let mut file = zip.by_index(i).unwrap();
+			let modified = file.last_modified().unwrap();
+			assert_eq!(
+				(modified.year(), modified.month(), modified.day(), modified.hour(), modified.minute(), modified.second()),
+				(1980, 1, 1, 0, 0, 0),
+			);
+			assert_eq!(file.unix_mode().map(|mode| mode & 0o777), Some(0o644));
+			assert_eq!(file.compression(), zip::CompressionMethod::Deflated);
+			let mut contents = String::new();
+			file.read_to_string(&mut contents).unwrap();
+			entries.push((file.name().to_string(), contents));
+		}
+		assert_eq!(entries, vec![
+			("index.sql".to_string(), "\\ir public/TABLES/accounts.sql\n".to_string()),
+			("manifest.tsv".to_string(), "path\tdesc\tschema\ttag\n".to_string()),
+			("public/TABLES/accounts.sql".to_string(), "CREATE TABLE accounts();\n\n".to_string()),
+			("public/TABLES/users.sql".to_string(), "CREATE TABLE users();\n".to_string()),
+		]);
+	}
+
+	#[test]
+	fn unsafe_names() {
+		for name in ["..", "a/b", ""] {
+			let mut root = split_dump();
+			root.files.insert(name.to_string(), vec![]);
+			let mut writer = TarOutputWriter::new(vec![]);
+			let err = writer.write_from_split_dump(&root, &mut Progress::new(None)).unwrap_err();
+			assert_eq!(err.to_string(), format!("unsafe file name {:?}", name));
 		}
 	}
 }
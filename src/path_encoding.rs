@@ -0,0 +1,337 @@
+// Turning object names into file names.
+//
+// Object names can contain pretty much anything, so before a name ends up in
+// a path, escape_path_component() makes it safe to use on any of the
+// platforms people are likely to check the output out on:
+//
+//   - '%', '/', '\', the characters Windows doesn't allow in file names
+//     (":*?"<>|") and control characters are replaced with '%' followed by
+//     the two hex digits of each byte of their UTF-8 representation, e.g.
+//     "a/b" becomes "a%2Fb"
+//   - a leading '.' (which would also make "." and ".." refer to directories
+//     and hide files on Unix-likes), and a trailing '.' or ' ' (which Windows
+//     silently drops) are escaped the same way
+//   - the first character of a name Windows reserves for devices, such as
+//     "con" or "lpt1", is escaped as well
+//
+// Apart from names which get shortened (see shorten_path_component()), the
+// original name can be recovered by replacing each escape sequence with the
+// byte it stands for.  The manifest written next to index.sql lists the
+// original name of every object in any case.
+
+use unicode_normalization::UnicodeNormalization;
+
+// Most file systems don't allow more bytes than this in a file name.
+pub const MAX_PATH_COMPONENT_LEN: usize = 255;
+
+const WINDOWS_RESERVED_NAMES: [&str; 22] = [
+	"CON", "PRN", "AUX", "NUL",
+	"COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+	"LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+fn push_escaped(escaped: &mut String, c: char) {
+	let mut buf = [0; 4];
+	for b in c.encode_utf8(&mut buf).bytes() {
+		escaped.push_str(&format!("%{:02X}", b));
+	}
+}
+
+// Escapes a name for use in a path as described above.
+pub fn escape_path_component(s: &str) -> String {
+	let stem = s.split('.').next().unwrap();
+	let reserved = WINDOWS_RESERVED_NAMES.iter().any(|name| name.eq_ignore_ascii_case(stem));
+
+	let last = s.chars().count().saturating_sub(1);
+	let mut escaped = String::with_capacity(s.len());
+	for (i, c) in s.chars().enumerate() {
+		let needs_escaping = match c {
+			'%' | '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => true,
+			c if c.is_control() => true,
+			'.' if i == 0 => true,
+			'.' | ' ' if i == last => true,
+			_ => i == 0 && reserved,
+		};
+		if needs_escaping {
+			push_escaped(&mut escaped, c);
+		} else {
+			escaped.push(c);
+		}
+	}
+	escaped
+}
+
+// Checks that a path component can't refer to anything but a file or
+// directory of that name in the directory it's in.
+pub fn is_safe_path_component(s: &str) -> bool {
+	!s.is_empty() &&
+		s != "." &&
+		s != ".." &&
+		!s.contains(|c: char| c == '/' || c == '\\' || c.is_control()) &&
+		s.len() <= MAX_PATH_COMPONENT_LEN
+}
+
+// 64-bit FNV-1a.  Used instead of std's hashers since the output has to stay
+// the same across Rust versions.
+fn fnv1a(s: &str) -> u64 {
+	let mut hash: u64 = 0xcbf29ce484222325;
+	for b in s.bytes() {
+		hash ^= b as u64;
+		hash = hash.wrapping_mul(0x100000001b3);
+	}
+	hash
+}
+
+// Makes sure a path component isn't too long for the file system.  Overlong
+// ones are cut short and get a hash of the whole thing appended, keeping the
+// extension (if any) intact, e.g. "aaaa…aaaa~0123456789abcdef.sql".
+pub fn shorten_path_component(s: String) -> String {
+	if s.len() <= MAX_PATH_COMPONENT_LEN {
+		return s;
+	}
+
+	let extension = match s.rfind('.') {
+		Some(pos) if s.len() - pos <= 16 => &s[pos..],
+		_ => "",
+	};
+	let suffix = format!("~{:016x}{}", fnv1a(&s), extension);
+
+	let mut prefix_len = MAX_PATH_COMPONENT_LEN - suffix.len();
+	while !s.is_char_boundary(prefix_len) {
+		prefix_len -= 1;
+	}
+	// Don't leave half an escape sequence behind.
+	if let Some(pos) = s[..prefix_len].rfind('%') {
+		if prefix_len - pos < 3 {
+			prefix_len = pos;
+		}
+	}
+
+	format!("{}{}", &s[..prefix_len], suffix)
+}
+
+// Returns the form of a path component a case-insensitive,
+// normalization-insensitive file system (such as the default ones on macOS and
+// Windows) would compare it in.  Two names with the same folded form refer to
+// the same file on such a file system.
+pub fn fold_path_component(s: &str) -> String {
+	s.nfd().collect::<String>().to_lowercase().nfc().collect()
+}
+
+// Gives a path component which collides with another one a different name by
+// appending a hash of it, keeping the extension (if any) intact, e.g.
+// "Users.sql" becomes "Users~0123456789abcdef.sql".
+pub fn disambiguate_path_component(s: &str) -> String {
+	let (stem, extension) = match s.rfind('.') {
+		Some(pos) if pos > 0 && s.len() - pos <= 16 => s.split_at(pos),
+		_ => (s, ""),
+	};
+	shorten_path_component(format!("{}~{:016x}{}", stem, fnv1a(s), extension))
+}
+
+// Encodes the tag of a function, procedure or aggregate, e.g.
+// "foo(integer, character varying)", into something more suitable for a file
+// name: "foo(integer,character_varying)".  Only the spaces outside double
+// quotes are touched: the ones separating the arguments are dropped, and the
+// ones in multi-word type names like "double precision" become underscores.
+// pg_dump schema-qualifies every type outside pg_catalog, so no other unquoted
+// type name can end up looking like one of those.  Quoted identifiers are left
+// alone, so that e.g. "A b" and "A_b" stay distinct.  The result still needs
+// to be escaped like any other name.
+pub fn encode_function_signature(tag: &str) -> String {
+	let (name, arguments) = match tag.split_once("(") {
+		None => return tag.to_string(),
+		Some(tup) => tup,
+	};
+	let arguments = arguments.strip_suffix(")").unwrap_or(arguments);
+
+	let mut encoded = String::with_capacity(arguments.len());
+	let mut in_quotes = false;
+	let mut chars = arguments.chars().peekable();
+	while let Some(c) = chars.next() {
+		match c {
+			// A doubled quote inside a quoted identifier toggles this twice.
+			'"' => in_quotes = !in_quotes,
+			',' if !in_quotes && chars.peek() == Some(&' ') => {
+				chars.next();
+			},
+			' ' if !in_quotes => {
+				encoded.push('_');
+				continue;
+			},
+			_ => {},
+		}
+		encoded.push(c);
+	}
+
+	format!("{}({})", name, encoded)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Recovers the original name from an escaped one, as described at the top
+	// of the file.
+	fn unescape(s: &str) -> String {
+		let mut bytes = Vec::new();
+		let mut rest = s.as_bytes();
+		while let Some((&b, tail)) = rest.split_first() {
+			if b == b'%' {
+				let hex = std::str::from_utf8(&tail[..2]).unwrap();
+				bytes.push(u8::from_str_radix(hex, 16).unwrap());
+				rest = &tail[2..];
+			} else {
+				bytes.push(b);
+				rest = tail;
+			}
+		}
+		String::from_utf8(bytes).unwrap()
+	}
+
+	#[test]
+	fn escape_special_characters() {
+		assert_eq!(escape_path_component("a/b"), "a%2Fb");
+		assert_eq!(escape_path_component("100%"), "100%25");
+		assert_eq!(escape_path_component("a\\b"), "a%5Cb");
+		assert_eq!(escape_path_component("a:b*c?d\"e<f>g|h"), "a%3Ab%2Ac%3Fd%22e%3Cf%3Eg%7Ch");
+		assert_eq!(escape_path_component("My Schema"), "My Schema");
+		assert_eq!(escape_path_component("caf\u{e9}"), "caf\u{e9}");
+	}
+
+	#[test]
+	fn escape_dots() {
+		assert_eq!(escape_path_component("."), "%2E");
+		assert_eq!(escape_path_component(".."), "%2E%2E");
+		assert_eq!(escape_path_component(".hidden"), "%2Ehidden");
+		assert_eq!(escape_path_component("a.b"), "a.b");
+	}
+
+	#[test]
+	fn escape_control_characters() {
+		assert_eq!(escape_path_component("a\nb"), "a%0Ab");
+		assert_eq!(escape_path_component("\t"), "%09");
+		assert_eq!(escape_path_component("a\u{7f}"), "a%7F");
+		// C1 controls are escaped byte by byte.
+		assert_eq!(escape_path_component("a\u{85}b"), "a%C2%85b");
+	}
+
+	#[test]
+	fn escape_trailing_dots_and_spaces() {
+		assert_eq!(escape_path_component("a."), "a%2E");
+		assert_eq!(escape_path_component("a "), "a%20");
+		assert_eq!(escape_path_component("a. "), "a.%20");
+		assert_eq!(escape_path_component(" a"), " a");
+	}
+
+	#[test]
+	fn escape_reserved_names() {
+		assert_eq!(escape_path_component("con"), "%63on");
+		assert_eq!(escape_path_component("CON.sql"), "%43ON.sql");
+		assert_eq!(escape_path_component("lpt1"), "%6Cpt1");
+		assert_eq!(escape_path_component("console"), "console");
+		assert_eq!(escape_path_component("com10"), "com10");
+	}
+
+	#[test]
+	fn escaped_names_are_safe() {
+		let names = ["", ".", "..", "a/b", "a\\b", "\n", "con", "x\u{0}y"];
+		for name in &names[1..] {
+			assert!(is_safe_path_component(&escape_path_component(name)), "{:?}", name);
+		}
+		assert!(!is_safe_path_component(&escape_path_component(names[0])));
+	}
+
+	#[test]
+	fn escape_round_trip() {
+		let names = [
+			"plain", "My Schema", "a/b", "100%", "%41", ".", "..", ".a.", "a ", "con", "Lpt9.txt",
+			"a\nb\u{7f}", "\u{85}", "caf\u{e9}/\u{1f600}", "a:b*c?d\"e<f>g|h\\",
+		];
+		for name in names {
+			assert_eq!(unescape(&escape_path_component(name)), name);
+		}
+	}
+
+	#[test]
+	fn shorten_short_names() {
+		let name = "a".repeat(MAX_PATH_COMPONENT_LEN);
+		assert_eq!(shorten_path_component(name.clone()), name);
+		assert_eq!(shorten_path_component("tbl.sql".to_string()), "tbl.sql");
+	}
+
+	#[test]
+	fn shorten_long_names() {
+		let name = format!("{}.sql", "a".repeat(300));
+		let shortened = shorten_path_component(name.clone());
+		assert_eq!(shortened.len(), MAX_PATH_COMPONENT_LEN);
+		// The hash must not change between runs or releases.
+		assert!(shortened.ends_with("a~0cd431d28d638cf7.sql"), "{}", shortened);
+		assert!(shortened.starts_with(&"a".repeat(200)));
+		assert_eq!(shorten_path_component(name), shortened);
+
+		// Names which only differ after the cut still end up different.
+		let other = format!("{}b.sql", "a".repeat(300));
+		assert_ne!(shorten_path_component(other), shortened);
+
+		// Without an extension.
+		let shortened = shorten_path_component("a".repeat(300));
+		assert_eq!(shortened.len(), MAX_PATH_COMPONENT_LEN);
+		assert!(is_safe_path_component(&shortened));
+	}
+
+	#[test]
+	fn shorten_keeps_characters_and_escapes_whole() {
+		let shortened = shorten_path_component(escape_path_component(&"/".repeat(100)));
+		assert!(shortened.len() <= MAX_PATH_COMPONENT_LEN);
+		let prefix = shortened.split('~').next().unwrap();
+		assert!(prefix.len().is_multiple_of(3), "{}", shortened);
+		assert_eq!(unescape(prefix), "/".repeat(prefix.len() / 3));
+
+		let shortened = shorten_path_component("\u{e9}".repeat(200));
+		assert!(shortened.len() <= MAX_PATH_COMPONENT_LEN);
+		assert!(shortened.split('~').next().unwrap().chars().all(|c| c == '\u{e9}'));
+	}
+
+	#[test]
+	fn function_signature_spaces() {
+		assert_eq!(encode_function_signature("foo()"), "foo()");
+		assert_eq!(
+			encode_function_signature("foo(integer, character varying)"),
+			"foo(integer,character_varying)",
+		);
+		assert_eq!(
+			encode_function_signature("foo(timestamp with time zone, double precision[])"),
+			"foo(timestamp_with_time_zone,double_precision[])",
+		);
+	}
+
+	#[test]
+	fn function_signature_quoted_identifiers() {
+		assert_eq!(encode_function_signature("f(\"A b\")"), "f(\"A b\")");
+		assert_eq!(
+			encode_function_signature("f(\"My Schema\".t, \"a, \"\"b\"\"\")"),
+			"f(\"My Schema\".t,\"a, \"\"b\"\"\")",
+		);
+	}
+
+	#[test]
+	fn function_signature_collisions() {
+		let signatures = [
+			"f(\"A b\")",
+			"f(\"A_b\")",
+			"f(\"My Schema\".t)",
+			"f(\"My_Schema\".t)",
+			"f(public.double_precision)",
+			"f(double precision)",
+			"f(\"a, b\")",
+			"f(\"a\", \"b\")",
+			"f(\"a,b\")",
+		];
+		for (i, a) in signatures.iter().enumerate() {
+			for b in &signatures[i + 1..] {
+				assert_ne!(encode_function_signature(a), encode_function_signature(b), "{} vs {}", a, b);
+			}
+		}
+	}
+}
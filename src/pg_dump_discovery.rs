@@ -0,0 +1,283 @@
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::progress::Progress;
+
+// Where the Debian packages install each major version's binaries.
+const DEBIAN_LIB_DIRECTORY: &str = "/usr/lib/postgresql";
+
+#[derive(Debug, Clone)]
+pub struct PgDumpBinary {
+	pub path: PathBuf,
+	// In the server_version_num format, e.g. 150004 for 15.4.
+	pub version_num: i32,
+}
+
+// Converts a version number into a comparable number for its major version,
+// e.g. 150004 into 1500 and 90624 into 906.
+fn major_version(version_num: i32) -> i32 {
+	if version_num >= 100000 {
+		return version_num / 10000 * 100;
+	}
+	version_num / 100
+}
+
+pub fn format_version(version_num: i32) -> String {
+	if version_num >= 100000 {
+		return format!("{}.{}", version_num / 10000, version_num % 10000);
+	}
+	format!("{}.{}.{}", version_num / 10000, version_num / 100 % 100, version_num % 100)
+}
+
+fn format_major_version(version_num: i32) -> String {
+	if version_num >= 100000 {
+		return format!("{}", version_num / 10000);
+	}
+	format!("{}.{}", version_num / 10000, version_num / 100 % 100)
+}
+
+// Parses the output of "pg_dump --version", e.g.
+// "pg_dump (PostgreSQL) 15.4 (Debian 15.4-1.pgdg120+1)" or
+// "pg_dump (PostgreSQL) 17beta1".
+fn parse_version_output(output: &str) -> Option<i32> {
+	let version = output.strip_prefix("pg_dump (PostgreSQL) ")?;
+	let version: String = version.chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+	let mut parts = version.split('.').map(|part| part.parse::<i32>());
+	let major = parts.next()?.ok()?;
+	let minor = parts.next().and_then(|part| part.ok()).unwrap_or(0);
+	if major >= 10 {
+		return Some(major * 10000 + minor);
+	}
+	let patch = parts.next().and_then(|part| part.ok()).unwrap_or(0);
+	Some(major * 10000 + minor * 100 + patch)
+}
+
+fn pg_dump_version(path: &Path) -> Result<i32, String> {
+	let output = match process::Command::new(path).arg("--version").stdin(process::Stdio::null()).output() {
+		Err(err) => return Err(format!("could not run {}: {}", path.display(), err)),
+		Ok(output) => output,
+	};
+	if !output.status.success() {
+		return Err(format!("{} --version failed: {}", path.display(), String::from_utf8_lossy(&output.stderr).trim()));
+	}
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	match parse_version_output(stdout.trim()) {
+		None => Err(format!("could not parse the version of {} from {:?}", path.display(), stdout.trim())),
+		Some(version_num) => Ok(version_num),
+	}
+}
+
+fn is_executable_file(path: &Path) -> bool {
+	match fs::metadata(path) {
+		Err(_) => false,
+		#[cfg(unix)]
+		Ok(metadata) => {
+			use std::os::unix::fs::PermissionsExt;
+			metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+		},
+		#[cfg(not(unix))]
+		Ok(metadata) => metadata.is_file(),
+	}
+}
+
+fn search_path(name: &str) -> Vec<PathBuf> {
+	match env::var_os("PATH") {
+		None => vec![],
+		Some(path) => env::split_paths(&path)
+			.map(|dir| dir.join(name))
+			.filter(|path| is_executable_file(path))
+			.collect(),
+	}
+}
+
+// Returns the paths of all the pg_dump binaries we know to look for, in order
+// of preference: from PATH, from "pg_config --bindir" and from the Debian
+// per-version directories.
+fn candidate_paths() -> Vec<PathBuf> {
+	let mut candidates = search_path("pg_dump");
+
+	for pg_config in search_path("pg_config") {
+		let output = process::Command::new(&pg_config).arg("--bindir").stdin(process::Stdio::null()).output();
+		if let Ok(output) = output {
+			if output.status.success() {
+				let bindir = String::from_utf8_lossy(&output.stdout).trim().to_string();
+				candidates.push(Path::new(&bindir).join("pg_dump"));
+			}
+		}
+	}
+
+	if let Ok(entries) = fs::read_dir(DEBIAN_LIB_DIRECTORY) {
+		let mut debian_candidates: Vec<PathBuf> = entries
+			.filter_map(|entry| entry.ok())
+			.map(|entry| entry.path().join("bin").join("pg_dump"))
+			.collect();
+		debian_candidates.sort();
+		candidates.extend(debian_candidates);
+	}
+
+	let mut seen = vec![];
+	let mut unique = vec![];
+	for candidate in candidates {
+		if !is_executable_file(&candidate) {
+			continue;
+		}
+		let canonical = fs::canonicalize(&candidate).unwrap_or(candidate.clone());
+		if seen.contains(&canonical) {
+			continue;
+		}
+		seen.push(canonical);
+		unique.push(candidate);
+	}
+	unique
+}
+
+fn too_old_error(binary: &PgDumpBinary, server_version_num: i32) -> String {
+	format!(
+		"pg_dump version {} ({}) is older than the server (version {}); pg_dump {} or newer is required",
+		format_version(binary.version_num),
+		binary.path.display(),
+		format_version(server_version_num),
+		format_major_version(server_version_num),
+	)
+}
+
+// Checks that the pg_dump binary given on the command line can dump a server
+// of the given version.
+pub fn check(path: &OsStr, server_version_num: i32) -> Result<PgDumpBinary, String> {
+	let path = PathBuf::from(path);
+	let binary = PgDumpBinary{
+		version_num: pg_dump_version(&path)?,
+		path,
+	};
+	if major_version(binary.version_num) < major_version(server_version_num) {
+		return Err(too_old_error(&binary, server_version_num));
+	}
+	Ok(binary)
+}
+
+// Looks for the newest pg_dump which can dump a server of the given version.
+pub fn find(server_version_num: i32, progress: &mut Progress) -> Result<PgDumpBinary, String> {
+	let mut binaries = vec![];
+	for path in candidate_paths() {
+		match pg_dump_version(&path) {
+			Err(err) => progress.warn(&format!("ignoring {}: {}", path.display(), err)),
+			Ok(version_num) => binaries.push(PgDumpBinary{
+				path,
+				version_num,
+			}),
+		}
+	}
+
+	choose(binaries, server_version_num)
+}
+
+// Picks the newest of the binaries found, as long as it's new enough.  The
+// first one found wins ties.
+fn choose(binaries: Vec<PgDumpBinary>, server_version_num: i32) -> Result<PgDumpBinary, String> {
+	let mut newest: Option<PgDumpBinary> = None;
+	for binary in binaries {
+		if newest.as_ref().is_none_or(|newest| binary.version_num > newest.version_num) {
+			newest = Some(binary);
+		}
+	}
+	match newest {
+		None => Err("could not find pg_dump in PATH, in \"pg_config --bindir\" or in /usr/lib/postgresql/*/bin; use --pg-dump-binary".to_string()),
+		Some(newest) if major_version(newest.version_num) < major_version(server_version_num) => {
+			Err(too_old_error(&newest, server_version_num))
+		},
+		Some(newest) => Ok(newest),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn binary(path: &str, version_num: i32) -> PgDumpBinary {
+		PgDumpBinary{
+			path: PathBuf::from(path),
+			version_num,
+		}
+	}
+
+	#[test]
+	fn version_output_parsing() {
+		assert_eq!(parse_version_output("pg_dump (PostgreSQL) 16.4"), Some(160004));
+		assert_eq!(parse_version_output("pg_dump (PostgreSQL) 15.4 (Debian 15.4-1.pgdg120+1)"), Some(150004));
+		assert_eq!(parse_version_output("pg_dump (PostgreSQL) 16.4 (Ubuntu 16.4-0ubuntu0.24.04.2)"), Some(160004));
+		assert_eq!(parse_version_output("pg_dump (PostgreSQL) 14.13 (Homebrew)"), Some(140013));
+		assert_eq!(parse_version_output("pg_dump (PostgreSQL) 17beta1"), Some(170000));
+		assert_eq!(parse_version_output("pg_dump (PostgreSQL) 18devel"), Some(180000));
+		assert_eq!(parse_version_output("pg_dump (PostgreSQL) 10.23"), Some(100023));
+		assert_eq!(parse_version_output("pg_dump (PostgreSQL) 9.6.24"), Some(90624));
+		assert_eq!(parse_version_output("pg_dump (PostgreSQL) 9.6beta2"), Some(90600));
+
+		assert_eq!(parse_version_output("psql (PostgreSQL) 16.4"), None);
+		assert_eq!(parse_version_output("pg_dump (PostgreSQL) devel"), None);
+		assert_eq!(parse_version_output(""), None);
+	}
+
+	#[test]
+	fn version_formatting() {
+		assert_eq!(format_version(160004), "16.4");
+		assert_eq!(format_version(90624), "9.6.24");
+		assert_eq!(format_major_version(160004), "16");
+		assert_eq!(format_major_version(90624), "9.6");
+		assert_eq!(major_version(160004), major_version(160000));
+		assert!(major_version(90624) < major_version(100000));
+		assert!(major_version(90624) > major_version(90524));
+	}
+
+	#[test]
+	fn choosing_the_newest() {
+		let binaries = vec![
+			binary("/usr/bin/pg_dump", 150004),
+			binary("/usr/lib/postgresql/17/bin/pg_dump", 170002),
+			binary("/usr/lib/postgresql/16/bin/pg_dump", 160004),
+		];
+		assert_eq!(choose(binaries.clone(), 150000).unwrap().path, PathBuf::from("/usr/lib/postgresql/17/bin/pg_dump"));
+		assert_eq!(choose(binaries.clone(), 170004).unwrap().version_num, 170002);
+
+		// Newer minor releases of the server are fine, newer major ones
+		// aren't.
+		let err = choose(binaries, 180000).unwrap_err();
+		assert_eq!(err, "pg_dump version 17.2 (/usr/lib/postgresql/17/bin/pg_dump) is older than the server (version 18.0); pg_dump 18 or newer is required");
+
+		// The first one found wins ties.
+		let binaries = vec![binary("/a/pg_dump", 160004), binary("/b/pg_dump", 160004)];
+		assert_eq!(choose(binaries, 160000).unwrap().path, PathBuf::from("/a/pg_dump"));
+
+		assert!(choose(vec![], 160000).unwrap_err().contains("use --pg-dump-binary"));
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn checking_a_binary() {
+		use std::os::unix::fs::PermissionsExt;
+
+		let dir = env::temp_dir().join(format!("pg_split_dump_test_{}_discovery", process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		let stub = |name: &str, script: &str| -> PathBuf {
+			let path = dir.join(name);
+			fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+			fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+			path
+		};
+
+		let path = stub("pg_dump_16", "echo 'pg_dump (PostgreSQL) 16.4 (Debian 16.4-1.pgdg120+2)'");
+		assert_eq!(check(path.as_os_str(), 160002).unwrap().version_num, 160004);
+		assert_eq!(check(path.as_os_str(), 90624).unwrap().version_num, 160004);
+		assert!(check(path.as_os_str(), 170000).unwrap_err().contains("pg_dump 17 or newer is required"));
+
+		let path = stub("not_pg_dump", "echo 'psql (PostgreSQL) 16.4'");
+		assert!(check(path.as_os_str(), 160000).unwrap_err().contains("could not parse the version"));
+		let path = stub("failing_pg_dump", "echo broken >&2; exit 1");
+		assert!(check(path.as_os_str(), 160000).unwrap_err().ends_with("--version failed: broken"));
+		assert!(check(dir.join("nonexistent").as_os_str(), 160000).unwrap_err().starts_with("could not run"));
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
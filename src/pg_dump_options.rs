@@ -0,0 +1,290 @@
+// Validation of the extra options passed on to pg_dump with --pg-dump-arg.
+// pg_dump has to produce a schema-only custom format archive on its standard
+// output from our snapshot, so anything which would change that, or which we
+// have an option of our own for, is rejected.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Verdict {
+	Allowed,
+	Rejected(&'static str),
+}
+
+use Verdict::*;
+
+const OUTPUT_FORMAT: Verdict = Rejected("pg_split_dump needs a custom format archive on pg_dump's standard output");
+const SNAPSHOT: Verdict = Rejected("pg_split_dump makes pg_dump use the snapshot it exported");
+const CONNECTION: Verdict = Rejected("connection options go into CONNINFO");
+const DATA: Verdict = Rejected("pg_split_dump only dumps the schema");
+const PLAIN_TEXT_ONLY: Verdict = Rejected("this option only affects plain text output");
+const FILTER: Verdict = Rejected("use pg_split_dump's own --schema, --exclude-schema, --table and --exclude-table");
+const NO_ACL: Verdict = Rejected("use pg_split_dump's own --no-acl");
+const NO_COMMENTS: Verdict = Rejected("use pg_split_dump's own --no-comments");
+const NO_SECURITY_LABELS: Verdict = Rejected("use pg_split_dump's own --no-security-labels");
+const NO_OWNER: Verdict = Rejected("use pg_split_dump's own --no-owner");
+const ENCODING: Verdict = Rejected("pg_split_dump makes pg_dump use UTF8");
+const UNSUPPORTED: Verdict = Rejected("not supported by pg_split_dump");
+
+// pg_dump's options as of PostgreSQL 18: (long name, short name, whether it
+// takes a value, verdict).
+const PG_DUMP_OPTIONS: [(&str, Option<char>, bool, Verdict); 79] = [
+	("attribute-inserts", None, false, DATA),
+	("binary-upgrade", None, false, UNSUPPORTED),
+	("blobs", Some('b'), false, DATA),
+	("clean", Some('c'), false, PLAIN_TEXT_ONLY),
+	("column-inserts", None, false, DATA),
+	("compress", Some('Z'), true, OUTPUT_FORMAT),
+	("create", Some('C'), false, PLAIN_TEXT_ONLY),
+	("data-only", Some('a'), false, DATA),
+	("dbname", Some('d'), true, CONNECTION),
+	("disable-dollar-quoting", None, false, Allowed),
+	("disable-triggers", None, false, DATA),
+	("enable-row-security", None, false, Allowed),
+	("encoding", Some('E'), true, ENCODING),
+	("exclude-extension", None, true, Allowed),
+	("exclude-schema", Some('N'), true, FILTER),
+	("exclude-table", Some('T'), true, FILTER),
+	("exclude-table-and-children", None, true, FILTER),
+	("exclude-table-data", None, true, Allowed),
+	("exclude-table-data-and-children", None, true, Allowed),
+	("extension", Some('e'), true, Allowed),
+	("extra-float-digits", None, true, Allowed),
+	("file", Some('f'), true, OUTPUT_FORMAT),
+	("filter", None, true, FILTER),
+	("format", Some('F'), true, OUTPUT_FORMAT),
+	("help", Some('?'), false, UNSUPPORTED),
+	("host", Some('h'), true, CONNECTION),
+	("if-exists", None, false, PLAIN_TEXT_ONLY),
+	("include-foreign-data", None, true, DATA),
+	("inserts", None, false, DATA),
+	("jobs", Some('j'), true, OUTPUT_FORMAT),
+	("large-objects", None, false, DATA),
+	("load-via-partition-root", None, false, DATA),
+	("lock-wait-timeout", None, true, Allowed),
+	("no-acl", None, false, NO_ACL),
+	("no-blobs", Some('B'), false, DATA),
+	("no-comments", None, false, NO_COMMENTS),
+	("no-data", None, false, Allowed),
+	("no-large-objects", None, false, DATA),
+	("no-owner", Some('O'), false, NO_OWNER),
+	("no-password", Some('w'), false, CONNECTION),
+	("no-policies", None, false, Allowed),
+	("no-privileges", Some('x'), false, NO_ACL),
+	("no-publications", None, false, Allowed),
+	("no-schema", None, false, DATA),
+	("no-security-labels", None, false, NO_SECURITY_LABELS),
+	("no-statistics", None, false, Allowed),
+	("no-subscriptions", None, false, Allowed),
+	("no-sync", None, false, Allowed),
+	("no-table-access-method", None, false, Allowed),
+	("no-tablespaces", None, false, Allowed),
+	("no-toast-compression", None, false, Allowed),
+	("no-unlogged-table-data", None, false, Allowed),
+	("on-conflict-do-nothing", None, false, DATA),
+	("password", Some('W'), false, CONNECTION),
+	("port", Some('p'), true, CONNECTION),
+	("quote-all-identifiers", None, false, Allowed),
+	("restrict-key", None, true, PLAIN_TEXT_ONLY),
+	("role", None, true, Allowed),
+	("rows-per-insert", None, true, DATA),
+	("schema", Some('n'), true, FILTER),
+	("schema-only", Some('s'), false, DATA),
+	("section", None, true, DATA),
+	("sequence-data", None, false, DATA),
+	("serializable-deferrable", None, false, SNAPSHOT),
+	("snapshot", None, true, SNAPSHOT),
+	("statistics", None, false, DATA),
+	("statistics-only", None, false, DATA),
+	("strict-names", None, false, Allowed),
+	("superuser", Some('S'), true, Allowed),
+	("sync-method", None, true, OUTPUT_FORMAT),
+	("table", Some('t'), true, FILTER),
+	("table-and-children", None, true, FILTER),
+	("use-set-session-authorization", None, false, Allowed),
+	("username", Some('U'), true, CONNECTION),
+	("verbose", Some('v'), false, Allowed),
+	("version", Some('V'), false, UNSUPPORTED),
+	("with-data", None, false, DATA),
+	("with-schema", None, false, DATA),
+	("with-statistics", None, false, DATA),
+];
+
+// Finds a long option by name, allowing unambiguous abbreviations the same
+// way getopt_long() (and therefore pg_dump) does.
+fn find_long_option(name: &str) -> Result<(&'static str, bool, Verdict), String> {
+	for (long_name, _, takes_value, verdict) in PG_DUMP_OPTIONS {
+		if long_name == name {
+			return Ok((long_name, takes_value, verdict));
+		}
+	}
+	let mut found = None;
+	for (long_name, _, takes_value, verdict) in PG_DUMP_OPTIONS {
+		if long_name.starts_with(name) {
+			if found.is_some() {
+				return Err(format!("pg_dump option --{} is ambiguous", name));
+			}
+			found = Some((long_name, takes_value, verdict));
+		}
+	}
+	match found {
+		None => Err(format!("unrecognized pg_dump option --{}", name)),
+		Some(found) => Ok(found),
+	}
+}
+
+fn find_short_option(c: char) -> Result<(&'static str, bool, Verdict), String> {
+	for (long_name, short_name, takes_value, verdict) in PG_DUMP_OPTIONS {
+		if short_name == Some(c) {
+			return Ok((long_name, takes_value, verdict));
+		}
+	}
+	Err(format!("unrecognized pg_dump option -{}", c))
+}
+
+fn check_verdict(long_name: &str, verdict: Verdict) -> Result<(), String> {
+	match verdict {
+		Allowed => Ok(()),
+		Rejected(reason) => Err(format!("pg_dump option --{} can't be used: {}", long_name, reason)),
+	}
+}
+
+// Checks a single --pg-dump-arg value.  Each one must be a complete option,
+// so values go in the same argument: "--lock-wait-timeout=10s" or "-eplpgsql".
+pub fn validate(arg: &str) -> Result<(), String> {
+	if let Some(rest) = arg.strip_prefix("--") {
+		let (name, value) = match rest.split_once('=') {
+			None => (rest, None),
+			Some((name, value)) => (name, Some(value)),
+		};
+		if name.is_empty() {
+			return Err(format!("invalid pg_dump option {:?}", arg));
+		}
+		let (long_name, takes_value, verdict) = find_long_option(name)?;
+		check_verdict(long_name, verdict)?;
+		if takes_value && value.is_none() {
+			return Err(format!("pg_dump option --{} requires a value; use --{}=VALUE", long_name, long_name));
+		}
+		if !takes_value && value.is_some() {
+			return Err(format!("pg_dump option --{} doesn't take a value", long_name));
+		}
+		return Ok(());
+	}
+
+	let Some(rest) = arg.strip_prefix('-').filter(|rest| !rest.is_empty()) else {
+		return Err(format!("{:?} is not a pg_dump option", arg));
+	};
+	let mut chars = rest.chars();
+	while let Some(c) = chars.next() {
+		let (long_name, takes_value, verdict) = find_short_option(c)?;
+		check_verdict(long_name, verdict)?;
+		if takes_value {
+			if chars.as_str() == "" {
+				return Err(format!("pg_dump option -{} requires a value; use -{}VALUE", c, c));
+			}
+			break;
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn options_are_sorted_and_unique() {
+		for pair in PG_DUMP_OPTIONS.windows(2) {
+			assert!(pair[0].0 < pair[1].0, "{} vs {}", pair[0].0, pair[1].0);
+		}
+		let mut short_names: Vec<char> = PG_DUMP_OPTIONS.iter().filter_map(|option| option.1).collect();
+		let count = short_names.len();
+		short_names.sort();
+		short_names.dedup();
+		assert_eq!(short_names.len(), count);
+	}
+
+	#[test]
+	fn allowed_options() {
+		for arg in [
+			"--lock-wait-timeout=10s",
+			"--no-publications",
+			"--no-tablespaces",
+			"--no-statistics",
+			"--quote-all-identifiers",
+			"--extension=plpgsql",
+			"-eplpgsql",
+			"-v",
+			"-vS=postgres",
+		] {
+			assert_eq!(validate(arg), Ok(()), "{}", arg);
+		}
+	}
+
+	#[test]
+	fn rejected_options() {
+		assert_eq!(
+			validate("--format=p"),
+			Err("pg_dump option --format can't be used: pg_split_dump needs a custom format archive on pg_dump's standard output".to_string()),
+		);
+		assert_eq!(
+			validate("-Fp"),
+			Err("pg_dump option --format can't be used: pg_split_dump needs a custom format archive on pg_dump's standard output".to_string()),
+		);
+		assert_eq!(
+			validate("--file=out.sql"),
+			Err("pg_dump option --file can't be used: pg_split_dump needs a custom format archive on pg_dump's standard output".to_string()),
+		);
+		assert_eq!(
+			validate("--encoding=LATIN1"),
+			Err("pg_dump option --encoding can't be used: pg_split_dump makes pg_dump use UTF8".to_string()),
+		);
+		assert_eq!(
+			validate("-vx"),
+			Err("pg_dump option --no-privileges can't be used: use pg_split_dump's own --no-acl".to_string()),
+		);
+		for arg in [
+			"-f", "-fout.sql", "-E", "-EUTF8", "--data-only", "--snapshot=x", "--host=h", "-d", "--table=t",
+			"--filter=f", "--clean", "--restrict-key=k", "--help", "--version",
+		] {
+			assert!(validate(arg).unwrap_err().contains("can't be used"), "{}", arg);
+		}
+	}
+
+	#[test]
+	fn statistics_options() {
+		for arg in ["--statistics", "--statistics-only", "--with-statistics", "--with-data", "--with-schema", "--no-schema"] {
+			assert_eq!(
+				validate(arg),
+				Err(format!("pg_dump option {} can't be used: pg_split_dump only dumps the schema", arg)),
+			);
+		}
+	}
+
+	#[test]
+	fn abbreviations() {
+		assert_eq!(validate("--no-pub"), Ok(()));
+		assert_eq!(validate("--lock-wait=1s"), Ok(()));
+		// An abbreviation gets the verdict of the option it stands for.
+		assert!(validate("--form=c").unwrap_err().starts_with("pg_dump option --format can't be used"));
+		// An exact match wins over the longer options it's a prefix of.
+		assert!(validate("--statistics").unwrap_err().starts_with("pg_dump option --statistics can't be used"));
+
+		for name in ["no", "no-s", "stat", "with", "ex", "exclude-table-d"] {
+			assert_eq!(validate(&format!("--{}", name)), Err(format!("pg_dump option --{} is ambiguous", name)), "{}", name);
+		}
+	}
+
+	#[test]
+	fn malformed_options() {
+		assert_eq!(validate("--nosuchoption"), Err("unrecognized pg_dump option --nosuchoption".to_string()));
+		assert_eq!(validate("-k"), Err("unrecognized pg_dump option -k".to_string()));
+		assert_eq!(validate("--"), Err("invalid pg_dump option \"--\"".to_string()));
+		assert_eq!(validate("-"), Err("\"-\" is not a pg_dump option".to_string()));
+		assert_eq!(validate("plpgsql"), Err("\"plpgsql\" is not a pg_dump option".to_string()));
+		assert_eq!(
+			validate("--lock-wait-timeout"),
+			Err("pg_dump option --lock-wait-timeout requires a value; use --lock-wait-timeout=VALUE".to_string()),
+		);
+		assert_eq!(validate("-e"), Err("pg_dump option -e requires a value; use -eVALUE".to_string()));
+		assert_eq!(validate("--no-sync=yes"), Err("pg_dump option --no-sync doesn't take a value".to_string()));
+	}
+}
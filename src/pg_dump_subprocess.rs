@@ -1,48 +1,282 @@
+// Keeps the explicit returns and field names of the original code.
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
 use std::ffi::OsStr;
+use std::fmt;
 use std::process;
 use std::io::{self, BufRead, BufReader, Read};
-use std::sync::mpsc::{Receiver, channel};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::mpsc::{RecvTimeoutError, Sender, channel};
 use std::thread;
+use std::time::{Duration, Instant};
+
+// How long pg_dump gets to exit after being asked to before it's killed
+// outright.
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+// How often the watchdog checks whether we've received a signal.
+const WATCHDOG_INTERVAL: Duration = Duration::from_millis(100);
+
+// The pid of the running pg_dump, so that the signal handler can take it down
+// with us; zero if there isn't one.
+static CHILD_PID: AtomicI32 = AtomicI32::new(0);
+// The signal we were interrupted by, if any.
+static RECEIVED_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+#[cfg(unix)]
+extern "C" fn handle_signal(signal: libc::c_int) {
+	let pid = CHILD_PID.load(Ordering::SeqCst);
+	if pid == 0 {
+		// Nothing to clean up; die the way we would have without a handler.
+		unsafe {
+			libc::signal(signal, libc::SIG_DFL);
+			libc::raise(signal);
+		}
+		return;
+	}
+	RECEIVED_SIGNAL.store(signal, Ordering::SeqCst);
+	// We'll notice pg_dump going away when its output ends.  If it doesn't,
+	// the watchdog kills it.
+	unsafe { libc::kill(pid, libc::SIGTERM) };
+}
+
+#[cfg(unix)]
+fn install_signal_handlers() {
+	for signal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+		unsafe { libc::signal(signal, handle_signal as extern "C" fn(libc::c_int) as libc::sighandler_t) };
+	}
+}
+
+#[cfg(not(unix))]
+fn install_signal_handlers() {
+}
+
+// Asks pg_dump to exit.  On SIGTERM it cancels its queries before exiting.
+#[cfg(unix)]
+fn terminate(pid: i32) {
+	unsafe { libc::kill(pid, libc::SIGTERM) };
+}
+
+#[cfg(not(unix))]
+fn terminate(_pid: i32) {
+}
+
+// Kills pg_dump if it didn't take the hint.
+#[cfg(unix)]
+fn kill(pid: i32) {
+	unsafe { libc::kill(pid, libc::SIGKILL) };
+}
+
+#[cfg(not(unix))]
+fn kill(_pid: i32) {
+}
+
+// Blocks until pg_dump has exited, but leaves it for Child::wait() to reap so
+// that its pid can't be reused by another process while something might
+// still send it a signal.  Gives up after timeout, if given, and returns
+// whether pg_dump has exited.
+#[cfg(unix)]
+fn wait_for_exit(pid: i32, timeout: Option<Duration>) -> bool {
+	let deadline = timeout.map(|timeout| Instant::now() + timeout);
+	let flags = match deadline {
+		None => libc::WEXITED | libc::WNOWAIT,
+		Some(_) => libc::WEXITED | libc::WNOWAIT | libc::WNOHANG,
+	};
+	loop {
+		let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+		let ret = unsafe { libc::waitid(libc::P_PID, pid as libc::id_t, &mut info, flags) };
+		if ret != 0 {
+			if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+				continue;
+			}
+			// Already reaped, most likely.
+			return true;
+		}
+		// With WNOHANG, a pid of zero means it hasn't exited yet.
+		if unsafe { info.si_pid() } != 0 {
+			return true;
+		}
+		match deadline {
+			Some(deadline) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+			_ => return false,
+		}
+	}
+}
+
+#[cfg(not(unix))]
+fn wait_for_exit(_pid: i32, _timeout: Option<Duration>) -> bool {
+	true
+}
+
+#[derive(Debug)]
+pub enum PgDumpError {
+	// pg_dump couldn't be started at all.
+	SpawnFailed(io::Error),
+	// Reading pg_dump's output failed.
+	IOError(io::Error),
+	// pg_dump exited unsuccessfully or was killed by a signal.
+	Failed{
+		status: process::ExitStatus,
+		stderr: Vec<String>,
+	},
+	// pg_dump didn't finish within --pg-dump-timeout, and was killed.
+	TimedOut{
+		timeout: Duration,
+		stderr: Vec<String>,
+	},
+	// We received a signal, and killed pg_dump.
+	Interrupted{
+		signal: i32,
+	},
+}
+
+impl PgDumpError {
+	// The exit status to exit with after this error.
+	pub fn exit_status(&self) -> i32 {
+		match self {
+			PgDumpError::Interrupted{signal} => 128 + signal,
+			_ => 1,
+		}
+	}
+}
+
+fn format_exit_status(status: &process::ExitStatus) -> String {
+	#[cfg(unix)]
+	{
+		use std::os::unix::process::ExitStatusExt;
+		if let Some(signal) = status.signal() {
+			return format!("was terminated by signal {}", signal);
+		}
+	}
+	match status.code() {
+		Some(code) => format!("exited with status {}", code),
+		None => format!("exited with {}", status),
+	}
+}
+
+fn write_stderr(f: &mut fmt::Formatter, stderr: &[String]) -> fmt::Result {
+	if stderr.is_empty() {
+		return Ok(());
+	}
+	writeln!(f, " with the following output:")?;
+	for line in stderr {
+		write!(f, "\n    {}", line)?;
+	}
+	Ok(())
+}
+
+impl fmt::Display for PgDumpError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			PgDumpError::SpawnFailed(err) => write!(f, "could not start pg_dump: {}", err),
+			PgDumpError::IOError(err) => write!(f, "could not read from pg_dump: {}", err),
+			PgDumpError::Failed{status, stderr} => {
+				write!(f, "pg_dump {}", format_exit_status(status))?;
+				write_stderr(f, stderr)
+			},
+			PgDumpError::TimedOut{timeout, stderr} => {
+				write!(f, "pg_dump did not finish within {} seconds, and was killed", timeout.as_secs())?;
+				write_stderr(f, stderr)
+			},
+			PgDumpError::Interrupted{signal} => write!(f, "interrupted by signal {}; pg_dump was killed", signal),
+		}
+	}
+}
+
+impl std::error::Error for PgDumpError {}
 
 pub struct PgDumpSubprocess {
 	child_process: process::Child,
-	stderr_reader: Option<thread::JoinHandle<Vec<String>>>,
-	stderr_thread_error_channel: Receiver<String>,
+	stderr_reader: Option<thread::JoinHandle<io::Result<Vec<String>>>>,
 	stdout: process::ChildStdout,
+	timeout: Option<Duration>,
+	// The thread enforcing the timeout and making sure pg_dump exits after we
+	// got a signal; dropping the sender stops it.
+	watchdog: Option<(Sender<()>, thread::JoinHandle<()>)>,
+	timed_out: Arc<AtomicBool>,
+	// Set once the child has exited and been waited for.
+	result: Option<Result<(), PgDumpError>>,
 }
 
 impl PgDumpSubprocess {
-	pub fn new(pg_dump_binary_path: &OsStr, conninfo: &str, snapshot_id: &str) -> Result<PgDumpSubprocess, ()> {
-		let child = process::Command::new(pg_dump_binary_path)
+	// extra_args are passed to pg_dump as they are, e.g. ["--no-acl"].  The
+	// password, if any, is passed in the environment.  If timeout is given,
+	// pg_dump is killed if it hasn't finished by then.
+	pub fn new(
+		pg_dump_binary_path: &OsStr,
+		conninfo: &str,
+		password: Option<&str>,
+		snapshot_id: &str,
+		extra_args: &[String],
+		timeout: Option<Duration>,
+	) -> Result<PgDumpSubprocess, PgDumpError> {
+		install_signal_handlers();
+
+		let mut command = process::Command::new(pg_dump_binary_path);
+		// The service has already been resolved into conninfo.
+		command.env_remove("PGSERVICE");
+		if let Some(password) = password {
+			command.env("PGPASSWORD", password);
+		}
+		let child = command
 			.arg("--schema-only")
 			.args(["--format", "custom"])
 			.args([&OsStr::new("--snapshot"), &OsStr::new(snapshot_id)])
+			// The files we write are UTF-8, whatever the client encoding
+			// in the environment or conninfo says.
+			.args(["--encoding", "UTF8"])
+			// Its stdin isn't a terminal it could prompt on; we'll have
+			// prompted already if asked to.
+			.arg("--no-password")
+			.args(extra_args)
 			.args([&OsStr::new("--dbname"), &OsStr::new(conninfo)])
 			.stdin(process::Stdio::null())
 			.stdout(process::Stdio::piped())
 			.stderr(process::Stdio::piped())
 			.spawn();
-		if let Err(e) = child {
-			panic!("could not start pg_split_dump: {}", e);
-		}
-		let mut child = child.unwrap();
+		let mut child = match child {
+			Err(err) => return Err(PgDumpError::SpawnFailed(err)),
+			Ok(child) => child,
+		};
+		let pid = child.id() as i32;
+		CHILD_PID.store(pid, Ordering::SeqCst);
 
 		// We need to organize a background thread to read the stderr output or
 		// there's a risk of deadlocking if the process decides to write a lot
 		// of data into stderr.
 		let stderr = child.stderr.take().unwrap();
-		let (tx, rx) = channel();
 		let stderr_reader = thread::spawn(move || {
 			let mut lines = Vec::new();
 			let br = BufReader::new(stderr);
 			for line in br.lines() {
-				if let Err(e) = line {
-					let _ = tx.send(e.to_string());
-					return Vec::new();
+				lines.push(line?);
+			}
+			Ok(lines)
+		});
+
+		let timed_out = Arc::new(AtomicBool::new(false));
+		let (tx, rx) = channel::<()>();
+		let watchdog_timed_out = timed_out.clone();
+		let watchdog = thread::spawn(move || {
+			let deadline = timeout.map(|timeout| Instant::now() + timeout);
+			loop {
+				if let Err(RecvTimeoutError::Disconnected) = rx.recv_timeout(WATCHDOG_INTERVAL) {
+					return;
+				}
+				// The signal handler has already asked pg_dump to exit.
+				if RECEIVED_SIGNAL.load(Ordering::SeqCst) != 0 {
+					break;
 				}
-				lines.push(line.unwrap());
+				if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+					watchdog_timed_out.store(true, Ordering::SeqCst);
+					terminate(pid);
+					break;
+				}
+			}
+			if let Err(RecvTimeoutError::Timeout) = rx.recv_timeout(KILL_GRACE_PERIOD) {
+				kill(pid);
 			}
-			return lines;
 		});
 
 		let stdout = child.stdout.take().unwrap();
@@ -51,41 +285,124 @@ impl PgDumpSubprocess {
 			PgDumpSubprocess{
 				child_process: child,
 				stderr_reader: Some(stderr_reader),
-				stderr_thread_error_channel: rx,
 				stdout: stdout,
+				timeout,
+				watchdog: Some((tx, watchdog)),
+				timed_out,
+				result: None,
 			},
 		)
 	}
+
+	// Waits for pg_dump to exit and reaps it once neither the watchdog nor the
+	// signal handler can signal it any more.
+	fn reap(&mut self) -> io::Result<process::ExitStatus> {
+		wait_for_exit(self.child_process.id() as i32, None);
+		CHILD_PID.store(0, Ordering::SeqCst);
+		if let Some((tx, handle)) = self.watchdog.take() {
+			drop(tx);
+			let _ = handle.join();
+		}
+		self.child_process.wait()
+	}
+
+	// Asks pg_dump to exit, kills it if it hasn't within KILL_GRACE_PERIOD,
+	// and reaps it.
+	fn terminate_and_reap(&mut self) -> io::Result<process::ExitStatus> {
+		let pid = self.child_process.id() as i32;
+		terminate(pid);
+		if !wait_for_exit(pid, Some(KILL_GRACE_PERIOD)) {
+			kill(pid);
+		}
+		self.reap()
+	}
+
+	// Waits for pg_dump to exit, and figures out whether it was successful.
+	fn wait(&mut self) -> Result<(), PgDumpError> {
+		let stderr = self.stderr_reader.take().unwrap().join().unwrap();
+		let status = self.reap();
+
+		let signal = RECEIVED_SIGNAL.load(Ordering::SeqCst);
+		if signal != 0 {
+			return Err(PgDumpError::Interrupted{signal});
+		}
+		let stderr = match stderr {
+			Err(err) => return Err(PgDumpError::IOError(err)),
+			Ok(stderr) => stderr,
+		};
+		let status = match status {
+			Err(err) => return Err(PgDumpError::IOError(err)),
+			Ok(status) => status,
+		};
+		if self.timed_out.load(Ordering::SeqCst) {
+			return Err(PgDumpError::TimedOut{
+				timeout: self.timeout.unwrap(),
+				stderr,
+			});
+		}
+		if !status.success() {
+			return Err(PgDumpError::Failed{
+				status,
+				stderr,
+			});
+		}
+		Ok(())
+	}
+
+	// Reads whatever is left of pg_dump's output and waits for it to exit.
+	// Returns the reason pg_dump failed, if it did; this should be checked
+	// even if reading the dump failed, since the failure is likely to be the
+	// reason.
+	pub fn finish(&mut self) -> Result<(), PgDumpError> {
+		if self.result.is_none() {
+			if let Err(err) = io::copy(&mut self.stdout, &mut io::sink()) {
+				// Reap it first; its stderr stays open for as long as it
+				// runs.
+				let _ = self.terminate_and_reap();
+				let _ = self.wait();
+				self.result = Some(Err(PgDumpError::IOError(err)));
+			} else {
+				self.result = Some(self.wait());
+			}
+		}
+		match self.result.replace(Ok(())) {
+			Some(result) => result,
+			None => Ok(()),
+		}
+	}
 }
 
 impl Read for PgDumpSubprocess {
 	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-		let res = self.stdout.read(buf);
-		if res.is_err() {
-			return res;
+		if let Some(result) = &self.result {
+			return match result {
+				Ok(()) => Ok(0),
+				Err(_) => Err(io::Error::other("pg_dump failed")),
+			};
 		}
-		let read_len = res.unwrap();
+
+		let read_len = self.stdout.read(buf)?;
 		if read_len > 0 {
-			// Check that the stderr thread hasn't failed.
-			if let Ok(_) = self.stderr_thread_error_channel.try_recv() {
-				panic!("poopoo");
-			}
 			return Ok(read_len);
 		}
 
-		// End of stream, we need to make sure the subprocess ran successfully
-		// and clean up.
-		let stderr_lines = self.stderr_reader.take().unwrap().join().unwrap();
-		let exit_status = self.child_process.wait().expect("child process wasn't running");
-		if !exit_status.success() {
-			eprintln!("ERROR:  pg_dump failed with the following output:");
-			eprintln!("");
-			for line in stderr_lines {
-				eprintln!("    {}", line);
-			}
-			// TODO: handle signals
-			process::exit(1);
+		// End of stream, we need to make sure the subprocess ran successfully.
+		// The details are left for finish() to report.
+		let result = self.wait();
+		let failed = result.is_err();
+		self.result = Some(result);
+		if failed {
+			return Err(io::Error::other("pg_dump failed"));
 		}
 		return Ok(0);
 	}
 }
+
+impl Drop for PgDumpSubprocess {
+	// Make sure pg_dump doesn't outlive us, e.g. if reading the dump failed.
+	fn drop(&mut self) {
+		if self.result.is_none() {
+			let _ = self.terminate_and_reap();
+		}
+	}
+}
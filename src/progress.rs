@@ -0,0 +1,318 @@
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProgressFormat {
+	// A status line on stderr, if it's a terminal.
+	Text,
+	// One JSON object per line on stderr, for programs to read.
+	Json,
+}
+
+impl ProgressFormat {
+	pub fn from_string(s: &str) -> Option<ProgressFormat> {
+		match s {
+			"text" => Some(ProgressFormat::Text),
+			"json" => Some(ProgressFormat::Json),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+	Connecting,
+	RunningPgDump,
+	ReadingToc,
+	WritingFiles,
+	Done,
+}
+
+impl Phase {
+	fn description(&self) -> &'static str {
+		match self {
+			Phase::Connecting => "connecting",
+			Phase::RunningPgDump => "running pg_dump",
+			Phase::ReadingToc => "reading TOC",
+			Phase::WritingFiles => "writing files",
+			Phase::Done => "done",
+		}
+	}
+
+	fn json_name(&self) -> &'static str {
+		match self {
+			Phase::Connecting => "connecting",
+			Phase::RunningPgDump => "running_pg_dump",
+			Phase::ReadingToc => "reading_toc",
+			Phase::WritingFiles => "writing_files",
+			Phase::Done => "done",
+		}
+	}
+}
+
+// Reports which phase we're in, and how far along in it, on stderr.  Updates
+// within a phase are rate limited, except for the first and the last one.
+pub struct Progress {
+	format: Option<ProgressFormat>,
+	started: Instant,
+	phase: Option<Phase>,
+	// Steps completed out of the total in the current phase, if it's counted.
+	done: u64,
+	total: Option<u64>,
+	last_update: Option<Instant>,
+	// Whether the text status line is waiting to be terminated.
+	line_open: bool,
+	// Where it all goes; stderr outside of tests.
+	output: Box<dyn Write>,
+}
+
+const TEXT_UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+const JSON_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+impl Progress {
+	// Text progress is only shown on a terminal; pass None to not report any
+	// progress.
+	pub fn new(format: Option<ProgressFormat>) -> Progress {
+		let format = match format {
+			Some(ProgressFormat::Text) if !io::stderr().is_terminal() => None,
+			format => format,
+		};
+		Progress{
+			format,
+			started: Instant::now(),
+			phase: None,
+			done: 0,
+			total: None,
+			last_update: None,
+			line_open: false,
+			output: Box::new(io::stderr()),
+		}
+	}
+
+	pub fn phase(&mut self, phase: Phase) {
+		self.start_phase(phase, None);
+	}
+
+	// Starts a phase consisting of total steps; call advance() after each.
+	pub fn counted_phase(&mut self, phase: Phase, total: u64) {
+		self.start_phase(phase, Some(total));
+	}
+
+	fn start_phase(&mut self, phase: Phase, total: Option<u64>) {
+		if self.format.is_none() {
+			return;
+		}
+		self.end_line();
+		self.phase = Some(phase);
+		self.done = 0;
+		self.total = total;
+		self.last_update = Some(Instant::now());
+		self.report();
+		if phase == Phase::Done {
+			self.end_line();
+		}
+	}
+
+	pub fn advance(&mut self) {
+		let (Some(format), Some(total)) = (self.format, self.total) else {
+			return;
+		};
+		self.done += 1;
+		let interval = match format {
+			ProgressFormat::Text => TEXT_UPDATE_INTERVAL,
+			ProgressFormat::Json => JSON_UPDATE_INTERVAL,
+		};
+		if self.done < total && self.last_update.is_some_and(|last_update| last_update.elapsed() < interval) {
+			return;
+		}
+		self.last_update = Some(Instant::now());
+		self.report();
+	}
+
+	// Terminates the status line, so that an error message can be printed.
+	pub fn end_line(&mut self) {
+		if self.line_open {
+			let _ = writeln!(self.output);
+			self.line_open = false;
+		}
+	}
+
+	// Prints a warning on a line of its own.  The status line reappears with
+	// the next update.
+	pub fn warn(&mut self, message: &str) {
+		self.end_line();
+		let _ = writeln!(self.output, "WARNING:  {}", message);
+	}
+
+	fn report(&mut self) {
+		let Some(phase) = self.phase else {
+			return;
+		};
+		let stderr = &mut self.output;
+		let _ = match (self.format, self.total) {
+			(None, _) => return,
+			(Some(ProgressFormat::Text), None) => {
+				self.line_open = true;
+				if phase == Phase::Done {
+					write!(stderr, "\r{}\x1b[K", phase.description())
+				} else {
+					write!(stderr, "\r{}...\x1b[K", phase.description())
+				}
+			},
+			(Some(ProgressFormat::Text), Some(total)) => {
+				self.line_open = true;
+				write!(stderr, "\r{}: {}/{}\x1b[K", phase.description(), self.done, total)
+			},
+			(Some(ProgressFormat::Json), None) => writeln!(
+				stderr,
+				"{{\"phase\":\"{}\",\"elapsed_ms\":{}}}",
+				phase.json_name(),
+				self.started.elapsed().as_millis(),
+			),
+			(Some(ProgressFormat::Json), Some(total)) => writeln!(
+				stderr,
+				"{{\"phase\":\"{}\",\"done\":{},\"total\":{},\"elapsed_ms\":{}}}",
+				phase.json_name(),
+				self.done,
+				total,
+				self.started.elapsed().as_millis(),
+			),
+		};
+		let _ = stderr.flush();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::cell::RefCell;
+	use std::rc::Rc;
+
+	use regex::Regex;
+
+	// Collects what's written, so that tests can look at it.
+	#[derive(Clone, Default)]
+	struct Output(Rc<RefCell<Vec<u8>>>);
+
+	impl Write for Output {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.0.borrow_mut().write(buf)
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	impl Output {
+		fn take(&self) -> String {
+			String::from_utf8(std::mem::take(&mut *self.0.borrow_mut())).unwrap()
+		}
+	}
+
+	fn capture(format: ProgressFormat) -> (Progress, Output) {
+		let output = Output::default();
+		let mut progress = Progress::new(None);
+		progress.format = Some(format);
+		progress.output = Box::new(output.clone());
+		(progress, output)
+	}
+
+	// Checks each line against a regular expression, with "MS" standing in
+	// for a number of milliseconds.
+	fn assert_lines(output: &str, expected: &[&str]) {
+		let lines: Vec<&str> = output.lines().collect();
+		assert_eq!(lines.len(), expected.len(), "{}", output);
+		for (line, expected) in lines.iter().zip(expected) {
+			let pattern = format!("^{}$", regex::escape(expected).replace("MS", "[0-9]+"));
+			assert!(Regex::new(&pattern).unwrap().is_match(line), "{:?} doesn't match {:?}", line, expected);
+		}
+	}
+
+	#[test]
+	fn json_lines() {
+		let (mut progress, output) = capture(ProgressFormat::Json);
+		progress.phase(Phase::Connecting);
+		progress.phase(Phase::RunningPgDump);
+		progress.counted_phase(Phase::ReadingToc, 2);
+		progress.advance();
+		progress.advance();
+		progress.counted_phase(Phase::WritingFiles, 0);
+		progress.phase(Phase::Done);
+		assert_lines(&output.take(), &[
+			r#"{"phase":"connecting","elapsed_ms":MS}"#,
+			r#"{"phase":"running_pg_dump","elapsed_ms":MS}"#,
+			r#"{"phase":"reading_toc","done":0,"total":2,"elapsed_ms":MS}"#,
+			r#"{"phase":"reading_toc","done":2,"total":2,"elapsed_ms":MS}"#,
+			r#"{"phase":"writing_files","done":0,"total":0,"elapsed_ms":MS}"#,
+			r#"{"phase":"done","elapsed_ms":MS}"#,
+		]);
+	}
+
+	#[test]
+	fn rate_limiting() {
+		let (mut progress, output) = capture(ProgressFormat::Json);
+		progress.counted_phase(Phase::ReadingToc, 1000);
+		for _ in 0..999 {
+			progress.advance();
+		}
+		// Only the first update got through so far.
+		assert_lines(&output.take(), &[r#"{"phase":"reading_toc","done":0,"total":1000,"elapsed_ms":MS}"#]);
+
+		// Once enough time has passed, the next one does as well.
+		progress.last_update = Some(Instant::now() - JSON_UPDATE_INTERVAL);
+		progress.advance();
+		assert_lines(&output.take(), &[r#"{"phase":"reading_toc","done":1000,"total":1000,"elapsed_ms":MS}"#]);
+
+		// The last one always gets through.
+		progress.counted_phase(Phase::WritingFiles, 3);
+		progress.advance();
+		progress.advance();
+		progress.advance();
+		assert_lines(&output.take(), &[
+			r#"{"phase":"writing_files","done":0,"total":3,"elapsed_ms":MS}"#,
+			r#"{"phase":"writing_files","done":3,"total":3,"elapsed_ms":MS}"#,
+		]);
+
+		// Phases which aren't counted don't report any steps.
+		progress.phase(Phase::RunningPgDump);
+		progress.advance();
+		assert_lines(&output.take(), &[r#"{"phase":"running_pg_dump","elapsed_ms":MS}"#]);
+	}
+
+	#[test]
+	fn text_status_line() {
+		let (mut progress, output) = capture(ProgressFormat::Text);
+		progress.phase(Phase::Connecting);
+		progress.counted_phase(Phase::ReadingToc, 2);
+		progress.advance();
+		progress.advance();
+		assert_eq!(output.take(), "\rconnecting...\x1b[K\n\rreading TOC: 0/2\x1b[K\rreading TOC: 2/2\x1b[K");
+
+		progress.end_line();
+		progress.end_line();
+		progress.phase(Phase::Done);
+		assert_eq!(output.take(), "\n\rdone\x1b[K\n");
+	}
+
+	#[test]
+	fn warnings() {
+		// A warning gets a line of its own, and the status line comes back
+		// with the next update.
+		let (mut progress, output) = capture(ProgressFormat::Text);
+		progress.counted_phase(Phase::ReadingToc, 2);
+		progress.warn("something is off");
+		progress.warn("something else is off");
+		progress.advance();
+		progress.advance();
+		assert_eq!(
+			output.take(),
+			"\rreading TOC: 0/2\x1b[K\nWARNING:  something is off\nWARNING:  something else is off\n\rreading TOC: 2/2\x1b[K",
+		);
+
+		let (mut progress, output) = capture(ProgressFormat::Json);
+		progress.phase(Phase::Connecting);
+		progress.warn("something is off");
+		assert_lines(&output.take(), &[r#"{"phase":"connecting","elapsed_ms":MS}"#, "WARNING:  something is off"]);
+	}
+}
@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::identifiers::{parse_identifier, quote_identifier, split_once_unquoted};
+
+// Words which can appear in place of a role name without referring to a role
+// of that name.
+const ROLE_SPECIFICATION_KEYWORDS: [&str; 4] = ["public", "current_role", "current_user", "session_user"];
+
+// A role map file looks like this:
+//
+//   [roles]
+//   app_stg = "app"
+//   "Reporting (staging)" = "reporting"
+//
+// Roles not mentioned in the file are left alone.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RoleMapFile {
+	#[serde(default)]
+	roles: HashMap<String, String>,
+}
+
+// Renames roles in the statements of a dump, so that dumps of databases
+// which only differ in the names of their roles can be compared.
+#[derive(Debug, Clone, Default)]
+pub struct RoleMap {
+	roles: HashMap<String, String>,
+}
+
+impl RoleMap {
+	pub fn load(path: &Path) -> Result<RoleMap, String> {
+		let contents = match fs::read_to_string(path) {
+			Err(err) => {
+				return Err(format!("could not read role map file {}: {}", path.display(), err));
+			},
+			Ok(contents) => contents,
+		};
+
+		let file: RoleMapFile = match toml::from_str(&contents) {
+			Err(err) => return Err(format!("invalid role map file {}: {}", path.display(), err)),
+			Ok(file) => file,
+		};
+		for (from, to) in &file.roles {
+			if from.is_empty() || to.is_empty() {
+				return Err(format!("invalid role map file {}: role names can't be empty", path.display()));
+			}
+		}
+
+		Ok(RoleMap{
+			roles: file.roles,
+		})
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.roles.len() == 0
+	}
+
+	pub fn map_role(&self, role: &str) -> String {
+		match self.roles.get(role) {
+			Some(mapped) => mapped.clone(),
+			None => role.to_string(),
+		}
+	}
+
+	// Rewrites the role names in the statements of a TOC entry.  Only
+	// understands the statements pg_dump puts into entries which refer to
+	// roles: GRANT, REVOKE, ALTER DEFAULT PRIVILEGES, SET SESSION
+	// AUTHORIZATION, CREATE POLICY and CREATE USER MAPPING.  Anything else is
+	// returned unchanged.
+	pub fn rewrite_statements(&self, sql: &str) -> String {
+		if self.is_empty() {
+			return sql.to_string();
+		}
+
+		let mut rewritten = vec![];
+		for statement in sql.split(";\n") {
+			rewritten.push(self.rewrite_statement(statement));
+		}
+		rewritten.join(";\n")
+	}
+
+	fn rewrite_statement(&self, statement: &str) -> String {
+		// Keep any leading newlines etc. intact.
+		let start = statement.len() - statement.trim_start().len();
+		let (prefix, statement) = statement.split_at(start);
+
+		let rewritten = if let Some(rest) = statement.strip_prefix("SET SESSION AUTHORIZATION ") {
+			format!("SET SESSION AUTHORIZATION {}", self.rewrite_role_list(rest))
+		} else if let Some(rest) = statement.strip_prefix("CREATE USER MAPPING FOR ") {
+			format!("CREATE USER MAPPING FOR {}", self.rewrite_role_list(rest))
+		} else if let Some(rest) = statement.strip_prefix("ALTER DEFAULT PRIVILEGES FOR ROLE ") {
+			let rest = self.rewrite_role_list(rest);
+			format!("ALTER DEFAULT PRIVILEGES FOR ROLE {}", self.rewrite_after_keyword(&rest, &[" TO ", " FROM "]))
+		} else if statement.starts_with("ALTER DEFAULT PRIVILEGES ") ||
+			statement.starts_with("GRANT ") ||
+			statement.starts_with("REVOKE ") {
+			self.rewrite_after_keyword(statement, &[" TO ", " FROM "])
+		} else if statement.starts_with("CREATE POLICY ") {
+			self.rewrite_after_keyword(statement, &[" TO "])
+		} else {
+			statement.to_string()
+		};
+		format!("{}{}", prefix, rewritten)
+	}
+
+	// Rewrites the role list following the first of the keywords found
+	// outside of quotes and parentheses.
+	fn rewrite_after_keyword(&self, statement: &str, keywords: &[&str]) -> String {
+		for keyword in keywords {
+			if let Some((head, rest)) = split_once_unquoted(statement, keyword) {
+				return format!("{}{}{}", head, keyword, self.rewrite_role_list(rest));
+			}
+		}
+		statement.to_string()
+	}
+
+	// Rewrites a comma-separated list of roles at the beginning of s, e.g.
+	// "foo, \"Bar\" WITH GRANT OPTION".  PUBLIC, CURRENT_USER and the like
+	// aren't roles, and roles which aren't mapped aren't touched either.
+	fn rewrite_role_list(&self, s: &str) -> String {
+		let mut rewritten = String::new();
+		let mut rest = s;
+		loop {
+			let trimmed = rest.trim_start();
+			rewritten.push_str(&rest[..rest.len() - trimmed.len()]);
+
+			let (role, remainder) = parse_identifier(trimmed);
+			let token = &trimmed[..trimmed.len() - remainder.len()];
+			let role = if trimmed.starts_with('"') {
+				Some(role)
+			} else {
+				let role = role.to_lowercase();
+				if ROLE_SPECIFICATION_KEYWORDS.contains(&role.as_str()) {
+					None
+				} else {
+					Some(role)
+				}
+			};
+			match role.and_then(|role| self.roles.get(&role)) {
+				None => rewritten.push_str(token),
+				Some(mapped) => rewritten.push_str(&quote_identifier(mapped)),
+			}
+
+			match remainder.strip_prefix(',') {
+				Some(remainder) => {
+					rewritten.push(',');
+					rest = remainder;
+				},
+				None => {
+					rewritten.push_str(remainder);
+					return rewritten;
+				},
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn role_map() -> RoleMap {
+		RoleMap{
+			roles: HashMap::from([
+				("app_stg".to_string(), "app".to_string()),
+				("Reporting (staging)".to_string(), "reporting".to_string()),
+				("dba".to_string(), "Admin Team".to_string()),
+			]),
+		}
+	}
+
+	#[test]
+	fn map_role() {
+		let map = role_map();
+		assert_eq!(map.map_role("app_stg"), "app");
+		assert_eq!(map.map_role("Reporting (staging)"), "reporting");
+		assert_eq!(map.map_role("someone_else"), "someone_else");
+		assert_eq!(map.map_role("APP_STG"), "APP_STG");
+	}
+
+	#[test]
+	fn grant_and_revoke() {
+		let map = role_map();
+		assert_eq!(
+			map.rewrite_statements("GRANT SELECT ON TABLE public.t TO app_stg;\n"),
+			"GRANT SELECT ON TABLE public.t TO app;\n",
+		);
+		assert_eq!(
+			map.rewrite_statements("GRANT ALL ON SCHEMA s TO \"Reporting (staging)\" WITH GRANT OPTION;\n"),
+			"GRANT ALL ON SCHEMA s TO reporting WITH GRANT OPTION;\n",
+		);
+		assert_eq!(
+			map.rewrite_statements("REVOKE ALL ON FUNCTION public.f(integer) FROM PUBLIC;\nGRANT ALL ON FUNCTION public.f(integer) TO dba, bob;\n"),
+			"REVOKE ALL ON FUNCTION public.f(integer) FROM PUBLIC;\nGRANT ALL ON FUNCTION public.f(integer) TO \"Admin Team\", bob;\n",
+		);
+		assert_eq!(
+			map.rewrite_statements("GRANT SELECT(a) ON TABLE public.t TO CURRENT_USER, SESSION_USER, current_role;\n"),
+			"GRANT SELECT(a) ON TABLE public.t TO CURRENT_USER, SESSION_USER, current_role;\n",
+		);
+	}
+
+	#[test]
+	fn quoted_role_names() {
+		let map = role_map();
+		// A quoted role named like a keyword is a role like any other.
+		let map = RoleMap{
+			roles: HashMap::from([
+				("PUBLIC".to_string(), "everyone".to_string()),
+				("current_user".to_string(), "me".to_string()),
+			]).into_iter().chain(map.roles).collect(),
+		};
+		assert_eq!(
+			map.rewrite_statements("GRANT USAGE ON SCHEMA s TO \"PUBLIC\", PUBLIC, \"current_user\", CURRENT_USER;\n"),
+			"GRANT USAGE ON SCHEMA s TO everyone, PUBLIC, me, CURRENT_USER;\n",
+		);
+		// Quoting which doesn't change the name is kept as is.
+		assert_eq!(
+			map.rewrite_statements("GRANT USAGE ON SCHEMA s TO \"bob\", \"Bob\";\n"),
+			"GRANT USAGE ON SCHEMA s TO \"bob\", \"Bob\";\n",
+		);
+		assert_eq!(
+			map.rewrite_statements("GRANT USAGE ON SCHEMA s TO \"app_stg\";\n"),
+			"GRANT USAGE ON SCHEMA s TO app;\n",
+		);
+	}
+
+	#[test]
+	fn default_privileges() {
+		let map = role_map();
+		assert_eq!(
+			map.rewrite_statements("ALTER DEFAULT PRIVILEGES FOR ROLE dba IN SCHEMA public GRANT SELECT ON TABLES TO app_stg;\n"),
+			"ALTER DEFAULT PRIVILEGES FOR ROLE \"Admin Team\" IN SCHEMA public GRANT SELECT ON TABLES TO app;\n",
+		);
+		assert_eq!(
+			map.rewrite_statements("ALTER DEFAULT PRIVILEGES FOR ROLE app_stg REVOKE ALL ON FUNCTIONS FROM PUBLIC;\n"),
+			"ALTER DEFAULT PRIVILEGES FOR ROLE app REVOKE ALL ON FUNCTIONS FROM PUBLIC;\n",
+		);
+	}
+
+	#[test]
+	fn session_authorization() {
+		let map = role_map();
+		assert_eq!(
+			map.rewrite_statements("SET SESSION AUTHORIZATION app_stg;\nGRANT SELECT ON TABLE public.t TO dba;\nRESET SESSION AUTHORIZATION;\n"),
+			"SET SESSION AUTHORIZATION app;\nGRANT SELECT ON TABLE public.t TO \"Admin Team\";\nRESET SESSION AUTHORIZATION;\n",
+		);
+	}
+
+	#[test]
+	fn policies() {
+		let map = role_map();
+		assert_eq!(
+			map.rewrite_statements("CREATE POLICY p ON public.t FOR SELECT TO app_stg, PUBLIC USING ((owner = CURRENT_USER));\n"),
+			"CREATE POLICY p ON public.t FOR SELECT TO app, PUBLIC USING ((owner = CURRENT_USER));\n",
+		);
+		// Role names in string literals and quoted identifiers aren't roles.
+		assert_eq!(
+			map.rewrite_statements("CREATE POLICY \"to app_stg\" ON public.t TO app_stg USING ((note = 'app_stg'::text));\n"),
+			"CREATE POLICY \"to app_stg\" ON public.t TO app USING ((note = 'app_stg'::text));\n",
+		);
+		assert_eq!(
+			map.rewrite_statements("CREATE POLICY p ON public.t USING ((note <> ' TO app_stg'::text));\n"),
+			"CREATE POLICY p ON public.t USING ((note <> ' TO app_stg'::text));\n",
+		);
+	}
+
+	#[test]
+	fn user_mappings() {
+		let map = role_map();
+		assert_eq!(
+			map.rewrite_statements("CREATE USER MAPPING FOR app_stg SERVER srv OPTIONS (\n    \"user\" 'app_stg'\n);\n"),
+			"CREATE USER MAPPING FOR app SERVER srv OPTIONS (\n    \"user\" 'app_stg'\n);\n",
+		);
+		assert_eq!(
+			map.rewrite_statements("CREATE USER MAPPING FOR PUBLIC SERVER srv;\n"),
+			"CREATE USER MAPPING FOR PUBLIC SERVER srv;\n",
+		);
+	}
+
+	#[test]
+	fn other_statements() {
+		let map = role_map();
+		let sql = "CREATE TABLE public.app_stg (\n    a integer\n);\n";
+		assert_eq!(map.rewrite_statements(sql), sql);
+		assert_eq!(RoleMap::default().rewrite_statements("GRANT ALL ON SCHEMA s TO app_stg;\n"), "GRANT ALL ON SCHEMA s TO app_stg;\n");
+	}
+}
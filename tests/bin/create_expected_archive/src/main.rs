@@ -77,17 +77,15 @@ fn create_expected_archive_from_directory(postgres_version: i32, archive: &mut t
 			if let Some(location) = directive.strip_prefix("-- !! LOC ") {
 				file_archive_path = Path::new(location).to_path_buf();
 			} else if let Some(version_expression) = directive.strip_prefix("-- !! VER ") {
-				// TODO, obviously
-				if version_expression == "< 12" {
-					if !(postgres_version < 12) {
-						continue 'outer;
-					}
-				} else if version_expression == ">= 12" {
-					if !(postgres_version >= 12) {
-						continue 'outer;
-					}
+				let matches = if let Some(version) = version_expression.strip_prefix("< ") {
+					postgres_version < version.parse::<i32>().unwrap()
+				} else if let Some(version) = version_expression.strip_prefix(">= ") {
+					postgres_version >= version.parse::<i32>().unwrap()
 				} else {
 					panic!("unexpected version expression {}", version_expression);
+				};
+				if !matches {
+					continue 'outer;
 				}
 			} else {
 				panic!("unknown directive {}", directive);
@@ -0,0 +1,5 @@
+CREATE DOMAIN "My Schema"."Positive" AS integer
+	CONSTRAINT "Positive_check" CHECK ((VALUE > 0));
+
+ALTER DOMAIN "My Schema"."Positive" OWNER TO postgres;
+
@@ -0,0 +1,3 @@
+ALTER TABLE ONLY "My Schema".order_line
+    ADD CONSTRAINT "order_line_Order_fkey" FOREIGN KEY ("Order") REFERENCES "My Schema"."order"("Id");
+
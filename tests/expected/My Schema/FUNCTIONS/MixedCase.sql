@@ -0,0 +1,6 @@
+CREATE FUNCTION "My Schema"."MixedCase"(a integer, b "My Schema"."order") RETURNS integer
+    LANGUAGE sql
+    AS $$ SELECT a $$;
+
+ALTER FUNCTION "My Schema"."MixedCase"(a integer, b "My Schema"."order") OWNER TO postgres;
+
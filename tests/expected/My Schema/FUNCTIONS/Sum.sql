@@ -0,0 +1,7 @@
+CREATE AGGREGATE "My Schema"."Sum"(integer) (
+    SFUNC = int4pl,
+    STYPE = integer
+);
+
+ALTER AGGREGATE "My Schema"."Sum"(integer) OWNER TO postgres;
+
@@ -0,0 +1,9 @@
+CREATE SEQUENCE "My Schema"."select"
+    START WITH 1
+    INCREMENT BY 1
+    NO MINVALUE
+    NO MAXVALUE
+    CACHE 1;
+
+ALTER SEQUENCE "My Schema"."select" OWNER TO postgres;
+
@@ -0,0 +1,9 @@
+CREATE TABLE "My Schema"."order" (
+    "Id" integer NOT NULL
+);
+
+ALTER TABLE "My Schema"."order" OWNER TO postgres;
+
+ALTER TABLE ONLY "My Schema"."order"
+    ADD CONSTRAINT order_pkey PRIMARY KEY ("Id");
+
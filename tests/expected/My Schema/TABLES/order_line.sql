@@ -0,0 +1,8 @@
+CREATE TABLE "My Schema".order_line (
+    "Order" integer
+);
+
+ALTER TABLE "My Schema".order_line OWNER TO postgres;
+
+GRANT SELECT ON TABLE "My Schema".order_line TO PUBLIC;
+
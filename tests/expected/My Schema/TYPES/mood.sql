@@ -0,0 +1,7 @@
+CREATE TYPE "My Schema".mood AS ENUM (
+    'sad',
+    'happy'
+);
+
+ALTER TYPE "My Schema".mood OWNER TO postgres;
+
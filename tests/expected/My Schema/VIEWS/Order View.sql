@@ -0,0 +1,5 @@
+CREATE OR REPLACE VIEW "My Schema"."Order View" ("Id") AS
+ SELECT 1 AS "Id";
+
+ALTER VIEW "My Schema"."Order View" OWNER TO postgres;
+
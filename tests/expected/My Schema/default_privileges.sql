@@ -0,0 +1,2 @@
+ALTER DEFAULT PRIVILEGES FOR ROLE postgres IN SCHEMA "My Schema" GRANT SELECT ON TABLES  TO PUBLIC;
+
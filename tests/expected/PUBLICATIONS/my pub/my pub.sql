@@ -0,0 +1,4 @@
+CREATE PUBLICATION "my pub" WITH (publish = 'insert, update, delete, truncate');
+
+ALTER PUBLICATION "my pub" OWNER TO postgres;
+
@@ -0,0 +1,2 @@
+ALTER PUBLICATION "my pub" ADD TABLE ONLY public.tbl_check_constraints;
+
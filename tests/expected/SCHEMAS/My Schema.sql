@@ -0,0 +1,4 @@
+CREATE SCHEMA "My Schema";
+
+ALTER SCHEMA "My Schema" OWNER TO postgres;
+
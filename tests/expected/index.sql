@@ -6,6 +6,23 @@ SET check_function_bodies = false;
 
 SELECT pg_catalog.set_config('search_path', '', false);
 
+\ir 'SCHEMAS/My Schema.sql'
+\ir 'My Schema/DOMAINS/Positive.sql'
+\ir 'My Schema/TYPES/mood.sql'
+\ir 'My Schema/TABLES/order.sql'
+\ir 'My Schema/FUNCTIONS/MixedCase.sql'
 \ir public/TRIGGER_FUNCTIONS/trigger_fn.sql
+\ir 'My Schema/FUNCTIONS/Sum.sql'
+\ir 'My Schema/VIEWS/Order View.sql'
+\ir 'My Schema/TABLES/order_line.sql'
+\ir 'My Schema/SEQUENCES/select.sql'
+\ir public/TABLES/Users.sql
 \ir public/TABLES/tbl_check_constraints.sql
+\ir public/VIEWS/checked_view.sql
+\ir public/TABLES/%63on.sql
 \ir public/TABLES/tbl_with_trigger_fn.sql
+\ir public/TABLES/users~4ba1a6b1bc3307e1.sql
+\ir 'My Schema/FK_CONSTRAINTS/order_line.sql'
+\ir 'PUBLICATIONS/my pub/my pub.sql'
+\ir 'PUBLICATIONS/my pub/tbl_check_constraints.sql'
+\ir 'My Schema/default_privileges.sql'
@@ -0,0 +1,6 @@
+CREATE TABLE public.con (
+    a integer
+);
+
+ALTER TABLE public.con OWNER TO postgres;
+
@@ -0,0 +1,6 @@
+CREATE TABLE public."Users" (
+    a integer
+);
+
+ALTER TABLE public."Users" OWNER TO postgres;
+
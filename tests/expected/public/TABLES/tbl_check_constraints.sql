@@ -7,3 +7,13 @@ CREATE TABLE public.tbl_check_constraints (
 
 ALTER TABLE public.tbl_check_constraints OWNER TO postgres;
 
+COMMENT ON CONSTRAINT a_b ON public.tbl_check_constraints IS 'a must be greater than b';
+
+GRANT SELECT ON TABLE public.tbl_check_constraints TO PUBLIC;
+
+GRANT UPDATE(b) ON TABLE public.tbl_check_constraints TO postgres;
+
+CREATE INDEX tbl_check_constraints_b ON public.tbl_check_constraints USING btree (b);
+
+CREATE POLICY "only postgres" ON public.tbl_check_constraints TO postgres USING ((CURRENT_USER = 'postgres'::name));
+
@@ -7,3 +7,5 @@ ALTER TABLE public.tbl_with_trigger_fn OWNER TO postgres;
 
 CREATE TRIGGER trigger AFTER DELETE ON public.tbl_with_trigger_fn FOR EACH ROW EXECUTE PROCEDURE public.trigger_fn();
 
+COMMENT ON TRIGGER trigger ON public.tbl_with_trigger_fn IS 'trigger';
+
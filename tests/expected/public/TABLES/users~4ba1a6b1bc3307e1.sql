@@ -0,0 +1,6 @@
+CREATE TABLE public.users (
+    a integer
+);
+
+ALTER TABLE public.users OWNER TO postgres;
+
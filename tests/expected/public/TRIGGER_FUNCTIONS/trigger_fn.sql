@@ -8,3 +8,5 @@ $$;
 
 ALTER FUNCTION public.trigger_fn() OWNER TO postgres;
 
+COMMENT ON FUNCTION public.trigger_fn() IS 'trigger function';
+
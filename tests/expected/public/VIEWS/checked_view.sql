@@ -0,0 +1,8 @@
+-- !! VER >= 16
+CREATE OR REPLACE VIEW public.checked_view ("Value") WITH (security_barrier='true') AS
+ SELECT a AS "Value"
+   FROM public.tbl_check_constraints
+  WITH CASCADED CHECK OPTION;
+
+ALTER VIEW public.checked_view OWNER TO postgres;
+
@@ -0,0 +1,9 @@
+-- !! VER < 16
+-- !! LOC public/VIEWS/checked_view.sql
+CREATE OR REPLACE VIEW public.checked_view ("Value") WITH (security_barrier='true') AS
+ SELECT tbl_check_constraints.a AS "Value"
+   FROM public.tbl_check_constraints
+  WITH CASCADED CHECK OPTION;
+
+ALTER VIEW public.checked_view OWNER TO postgres;
+
@@ -0,0 +1,11 @@
+CREATE SCHEMA ext_schema;
+
+CREATE FUNCTION ext_schema.ext_fn()
+RETURNS integer
+LANGUAGE sql
+AS $$ SELECT 1 $$;
+
+CREATE FUNCTION public.ext_public_fn()
+RETURNS integer
+LANGUAGE sql
+AS $$ SELECT 1 $$;
@@ -20,3 +20,70 @@ CREATE TABLE tbl_check_constraints(
     b integer,
     CONSTRAINT a_b CHECK (a > b)
 );
+
+COMMENT ON FUNCTION trigger_fn() IS 'trigger function';
+COMMENT ON TRIGGER trigger ON tbl_with_trigger_fn IS 'trigger';
+COMMENT ON CONSTRAINT a_b ON tbl_check_constraints IS 'a must be greater than b';
+
+-- "con" is a reserved file name on Windows
+CREATE TABLE con(
+    a integer
+);
+
+-- These would end up in the same file on a case-insensitive file system.
+CREATE TABLE users(
+    a integer
+);
+CREATE TABLE "Users"(
+    a integer
+);
+
+-- Names which need quoting
+CREATE SCHEMA "My Schema";
+
+CREATE TABLE "My Schema"."order"(
+    "Id" integer PRIMARY KEY
+);
+
+CREATE VIEW "My Schema"."Order View" AS
+SELECT 1 AS "Id";
+
+CREATE FUNCTION "My Schema"."MixedCase"(a integer, b "My Schema"."order")
+RETURNS integer
+LANGUAGE sql
+AS $$ SELECT a $$;
+
+CREATE SEQUENCE "My Schema"."select";
+
+CREATE VIEW checked_view("Value") WITH (security_barrier) AS
+SELECT a AS x FROM tbl_check_constraints
+WITH CASCADED CHECK OPTION;
+
+CREATE TYPE "My Schema".mood AS ENUM ('sad', 'happy');
+
+CREATE DOMAIN "My Schema"."Positive" AS integer CHECK (VALUE > 0);
+
+CREATE AGGREGATE "My Schema"."Sum"(integer) (
+    SFUNC = int4pl,
+    STYPE = integer
+);
+
+-- Privileges, default privileges and a policy; the string literal in the
+-- policy mustn't be touched by --role-map.
+GRANT SELECT ON TABLE tbl_check_constraints TO PUBLIC;
+GRANT UPDATE (b) ON TABLE tbl_check_constraints TO postgres;
+
+ALTER DEFAULT PRIVILEGES IN SCHEMA "My Schema" GRANT SELECT ON TABLES TO PUBLIC;
+
+CREATE POLICY "only postgres" ON tbl_check_constraints TO postgres
+USING (current_user = 'postgres');
+
+-- The tags of a publication's tables don't quote its name.
+CREATE PUBLICATION "my pub" FOR TABLE tbl_check_constraints;
+
+-- An index and a foreign key, for trying out other layouts.
+CREATE INDEX tbl_check_constraints_b ON tbl_check_constraints (b);
+
+CREATE TABLE "My Schema".order_line(
+    "Order" integer REFERENCES "My Schema"."order"
+);
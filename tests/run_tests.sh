@@ -32,3 +32,355 @@ if [ -s tmp/diff ]; then
     cat tmp/diff >&1
     exit 1
 fi
+
+fail() {
+    set +x
+    echo "pg_split_dump tests FAILED: $1" >&1
+    exit 1
+}
+
+# Checks that file contains line.
+expect_line() {
+    grep -qxF -- "$2" "$1" || fail "\"$1\" does not contain the line: $2"
+}
+
+# Checks that no file under the directory matches the regular expression.
+expect_no_match() {
+    if grep -rqE -- "$2" "$1"; then
+        fail "\"$1\" contains unexpected matches for $2: $(grep -rlE -- "$2" "$1" | tr '\n' ' ')"
+    fi
+}
+
+# The archive formats hold the same files as the directory output, whether
+# chosen by suffix or with --format, and a tar archive can go to stdout.
+RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/dir
+
+# Extracts an archive into a directory and compares it with tmp/dir.
+expect_same_as_directory() {
+    mkdir "$2"
+    tar -xf "$1" -C "$2" ${3:-}
+    diff -r tmp/dir "$2" > "$2.diff" || fail "$1 differs from the directory output: $(cat "$2.diff")"
+}
+
+RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/archive.tar
+expect_same_as_directory tmp/archive.tar tmp/archive_tar
+
+RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" "user=$PGUSER host=$PGHOST dbname=$DBNAME" - > tmp/stdout.tar
+expect_same_as_directory tmp/stdout.tar tmp/stdout_tar
+
+RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/archive.tar.gz
+expect_same_as_directory tmp/archive.tar.gz tmp/archive_tar_gz -z
+RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" --compression-level=1 "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/archive.tgz
+expect_same_as_directory tmp/archive.tgz tmp/archive_tgz -z
+RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" --format=g "user=$PGUSER host=$PGHOST dbname=$DBNAME" - > tmp/stdout.tar.gz
+expect_same_as_directory tmp/stdout.tar.gz tmp/stdout_tar_gz -z
+
+# tar needs the zstd program for these.
+if which zstd > /dev/null; then
+    RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/archive.tar.zst
+    expect_same_as_directory tmp/archive.tar.zst tmp/archive_tar_zst --zstd
+    RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" --format=s --compression-level=19 "user=$PGUSER host=$PGHOST dbname=$DBNAME" - > tmp/stdout.tar.zst
+    expect_same_as_directory tmp/stdout.tar.zst tmp/stdout_tar_zst --zstd
+else
+    echo "skipping the .tar.zst tests: zstd is not installed"
+fi
+
+for args in "--compression-level=10 tmp/level.tar.gz" "--compression-level=0 tmp/level.tar.zst" "--compression-level=1 tmp/level.tar"; do
+    status=0
+    ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" $args "user=$PGUSER host=$PGHOST dbname=$DBNAME" 2> tmp/level.log || status=$?
+    [ $status -eq 1 ] || fail "exit status $status with $args, expected 1"
+    grep -qE "compression level must be between|can only be used with compressed tar archives" tmp/level.log || fail "unexpected error with $args: $(cat tmp/level.log)"
+done
+if ls tmp/level.* 2> /dev/null | grep -v '\.log$'; then
+    fail "output was written despite an invalid compression level"
+fi
+
+# Zip archives come out the same every time, and need a file to write to.
+if which unzip > /dev/null; then
+    RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/archive.zip
+    RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" --format=z "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/archive2
+    cmp tmp/archive.zip tmp/archive2 || fail "two zip archives of the same database differ"
+    mkdir tmp/archive_zip
+    unzip -q tmp/archive.zip -d tmp/archive_zip
+    diff -r tmp/dir tmp/archive_zip > tmp/archive_zip.diff || fail "tmp/archive.zip differs from the directory output: $(cat tmp/archive_zip.diff)"
+    unzip -Z -T tmp/archive.zip | grep -vE '^(Archive:|Zip file size:|[0-9]+ files?,)' | grep -vF ' 19800101.000000 ' && fail "tmp/archive.zip has entries not dated 1980-01-01"
+    # Each directory's files come first, then its subdirectories, both sorted.
+    walk_sorted() {
+        (cd "$1" && find . -maxdepth 1 -type f | sed 's|^\./||' | LC_ALL=C sort | sed "s|^|$2|")
+        (cd "$1" && find . -mindepth 1 -maxdepth 1 -type d | sed 's|^\./||' | LC_ALL=C sort) | while read -r subdir; do
+            walk_sorted "$1/$subdir" "$2$subdir/"
+        done
+    }
+    [ "$(unzip -Z1 tmp/archive.zip)" = "$(walk_sorted tmp/dir "")" ] || fail "the entries of tmp/archive.zip are not sorted: $(unzip -Z1 tmp/archive.zip)"
+else
+    echo "skipping the zip tests: unzip is not installed"
+fi
+
+status=0
+../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" --format=z "user=$PGUSER host=$PGHOST dbname=$DBNAME" - > tmp/stdout.zip 2> tmp/stdout_zip.log || status=$?
+[ $status -eq 1 ] || fail "exit status $status with a zip archive on stdout, expected 1"
+grep -qF "only tar archives can be written to stdout" tmp/stdout_zip.log || fail "unexpected error with a zip archive on stdout: $(cat tmp/stdout_zip.log)"
+[ ! -s tmp/stdout.zip ] || fail "something was written to stdout despite the error"
+
+# --layout: foreign keys in the table's file, indexes in a directory of their
+# own and lowercase directory names; everything else as usual.
+cat > tmp/layout.toml <<'LAYOUT'
+[paths]
+"TABLE" = "{schema}/tables/{name}.sql"
+"CONSTRAINT" = "{schema}/tables/{table}.sql"
+"FK CONSTRAINT" = "{schema}/tables/{table}.sql"
+"INDEX" = "{schema}/indexes/{name}.sql"
+"FUNCTION" = "{schema}/functions/{function}.sql"
+LAYOUT
+RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" --layout=tmp/layout.toml "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/layout
+
+expect_line "tmp/layout/My Schema/tables/order_line.sql" '    ADD CONSTRAINT "order_line_Order_fkey" FOREIGN KEY ("Order") REFERENCES "My Schema"."order"("Id");'
+expect_line tmp/layout/public/indexes/tbl_check_constraints_b.sql 'CREATE INDEX tbl_check_constraints_b ON public.tbl_check_constraints USING btree (b);'
+# Setting FUNCTION also covers trigger functions.
+expect_line tmp/layout/manifest.tsv "$(printf 'public/functions/trigger_fn.sql\tFUNCTION\tpublic\ttrigger_fn()')"
+expect_line tmp/layout/manifest.tsv "$(printf 'public/VIEWS/checked_view.sql\tVIEW\tpublic\tchecked_view')"
+for dir in "My Schema/FK_CONSTRAINTS" "My Schema/TABLES" public/TABLES public/TRIGGER_FUNCTIONS; do
+    if [ -e "tmp/layout/$dir" ]; then
+        fail "tmp/layout/$dir exists despite --layout"
+    fi
+done
+
+# An invalid layout file is an error.
+printf '[paths]\n"TABLES" = "{schema}/{name}.sql"\n' > tmp/invalid_layout.toml
+status=0
+../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" --layout=tmp/invalid_layout.toml "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/invalid_layout 2> tmp/invalid_layout.log || status=$?
+[ $status -eq 1 ] || fail "exit status $status with an invalid layout file, expected 1"
+grep -qF 'invalid key "TABLES": unknown desc "TABLES"' tmp/invalid_layout.log || fail "unexpected error for an invalid layout: $(cat tmp/invalid_layout.log)"
+
+# Objects which can't be placed go into UNCLASSIFIED/, and the exit status
+# says so; with --strict, they're an error.  A layout which refers to a
+# variable views don't have is a convenient way to get some.
+cat > tmp/unplaceable_layout.toml <<'LAYOUT'
+[paths]
+"VIEW" = "{schema}/VIEWS/{server}.sql"
+LAYOUT
+status=0
+../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" --layout=tmp/unplaceable_layout.toml "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/unclassified 2> tmp/unclassified.log || status=$?
+[ $status -eq 2 ] || fail "exit status $status with objects in UNCLASSIFIED/, expected 2"
+grep -qF "WARNING:  2 objects could not be classified" tmp/unclassified.log || fail "no warning about the unclassified objects: $(cat tmp/unclassified.log)"
+expect_line tmp/unclassified/manifest.tsv "$(printf 'UNCLASSIFIED/VIEW/checked_view.sql\tVIEW\tpublic\tchecked_view')"
+expect_line tmp/unclassified/manifest.tsv "$(printf 'UNCLASSIFIED/VIEW/Order View.sql\tVIEW\tMy Schema\tOrder View')"
+
+status=0
+../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" --layout=tmp/unplaceable_layout.toml --strict "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/strict 2> tmp/strict.log || status=$?
+[ $status -eq 1 ] || fail "exit status $status with --strict, expected 1"
+grep -qF "variable {server} is not available" tmp/strict.log || fail "unexpected --strict error: $(cat tmp/strict.log)"
+if [ -e tmp/strict ]; then
+    fail "output was written despite --strict"
+fi
+
+# Filtering by kind doesn't take the other kinds of objects belonging to a
+# table with it: --only-kind=TRIGGER still gets the triggers (and their
+# comments), and --exclude-kind=TABLE keeps the indexes and policies.
+RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" --only-kind=TRIGGER "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/only_trigger
+[ "$(tail -n +2 tmp/only_trigger/manifest.tsv)" = "$(printf 'public/TABLES/tbl_with_trigger_fn.sql\tTRIGGER\tpublic\ttbl_with_trigger_fn trigger')" ] || fail "unexpected objects with --only-kind=TRIGGER: $(cat tmp/only_trigger/manifest.tsv)"
+expect_line tmp/only_trigger/public/TABLES/tbl_with_trigger_fn.sql 'CREATE TRIGGER trigger AFTER DELETE ON public.tbl_with_trigger_fn FOR EACH ROW EXECUTE FUNCTION public.trigger_fn();'
+expect_line tmp/only_trigger/public/TABLES/tbl_with_trigger_fn.sql "COMMENT ON TRIGGER trigger ON public.tbl_with_trigger_fn IS 'trigger';"
+
+RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" --exclude-kind=TABLE "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/exclude_table
+expect_no_match tmp/exclude_table '^CREATE TABLE '
+expect_no_match tmp/exclude_table/manifest.tsv '	TABLE	'
+expect_line tmp/exclude_table/public/TABLES/tbl_check_constraints.sql 'CREATE INDEX tbl_check_constraints_b ON public.tbl_check_constraints USING btree (b);'
+expect_line tmp/exclude_table/public/TABLES/tbl_check_constraints.sql 'CREATE POLICY "only postgres" ON public.tbl_check_constraints TO postgres USING ((CURRENT_USER = '"'postgres'"'::name));'
+expect_line tmp/exclude_table/public/TABLES/tbl_with_trigger_fn.sql 'CREATE TRIGGER trigger AFTER DELETE ON public.tbl_with_trigger_fn FOR EACH ROW EXECUTE FUNCTION public.trigger_fn();'
+expect_line "tmp/exclude_table/My Schema/FK_CONSTRAINTS/order_line.sql" '    ADD CONSTRAINT "order_line_Order_fkey" FOREIGN KEY ("Order") REFERENCES "My Schema"."order"("Id");'
+expect_line tmp/exclude_table/manifest.tsv "$(printf 'public/VIEWS/checked_view.sql\tVIEW\tpublic\tchecked_view')"
+
+# Leaving out a table by name does take its indexes, triggers etc. with it.
+RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" --exclude-table=tbl_with_trigger_fn "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/exclude_trigger_table
+if [ -e tmp/exclude_trigger_table/public/TABLES/tbl_with_trigger_fn.sql ]; then
+    fail "the trigger of a table left out with --exclude-table was written"
+fi
+
+# --role-map
+cat > tmp/role_map.toml <<'ROLES'
+[roles]
+postgres = "Mapped Owner"
+ROLES
+RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" --role-map=tmp/role_map.toml "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/role_map
+
+expect_no_match tmp/role_map '(TO|FOR ROLE) postgres\b'
+expect_line tmp/role_map/public/TABLES/tbl_check_constraints.sql 'ALTER TABLE public.tbl_check_constraints OWNER TO "Mapped Owner";'
+expect_line tmp/role_map/public/TABLES/tbl_check_constraints.sql 'GRANT SELECT ON TABLE public.tbl_check_constraints TO PUBLIC;'
+expect_line tmp/role_map/public/TABLES/tbl_check_constraints.sql 'GRANT UPDATE(b) ON TABLE public.tbl_check_constraints TO "Mapped Owner";'
+expect_line tmp/role_map/public/TABLES/tbl_check_constraints.sql 'CREATE POLICY "only postgres" ON public.tbl_check_constraints TO "Mapped Owner" USING ((CURRENT_USER = '"'postgres'"'::name));'
+expect_line "tmp/role_map/My Schema/default_privileges.sql" 'ALTER DEFAULT PRIVILEGES FOR ROLE "Mapped Owner" IN SCHEMA "My Schema" GRANT SELECT ON TABLES  TO PUBLIC;'
+expect_line "tmp/role_map/My Schema/FUNCTIONS/MixedCase.sql" 'ALTER FUNCTION "My Schema"."MixedCase"(a integer, b "My Schema"."order") OWNER TO "Mapped Owner";'
+
+# --no-acl, --no-comments and --no-security-labels, both with a live pg_dump
+# and with a dump taken without them, which a stub pg_dump hands over as is.
+"$PG_DUMP" --schema-only --format=custom --file=tmp/full.dump "user=$PGUSER host=$PGHOST dbname=$DBNAME"
+cat > tmp/stub_pg_dump <<STUB
+#!/bin/bash
+if [ "\$1" = "--version" ]; then
+    exec "$PG_DUMP" --version
+fi
+exec cat "$(pwd)/tmp/full.dump"
+STUB
+chmod +x tmp/stub_pg_dump
+
+for source in live offline; do
+    if [ $source = live ]; then
+        binary="$PG_DUMP"
+    else
+        binary=tmp/stub_pg_dump
+    fi
+    RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$binary" --no-acl --no-comments --no-security-labels "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/no_acl_$source
+
+    if [ -e "tmp/no_acl_$source/My Schema/default_privileges.sql" ]; then
+        fail "default privileges were written despite --no-acl ($source)"
+    fi
+    expect_no_match tmp/no_acl_$source '^(GRANT|REVOKE|ALTER DEFAULT PRIVILEGES|COMMENT ON|SECURITY LABEL) '
+    expect_no_match tmp/no_acl_$source/manifest.tsv '	(ACL|DEFAULT ACL|COMMENT|SECURITY LABEL)	'
+    expect_line tmp/no_acl_$source/public/TABLES/tbl_check_constraints.sql 'ALTER TABLE public.tbl_check_constraints OWNER TO postgres;'
+done
+# Make sure the stub's dump did have something to leave out.
+for desc in ACL "DEFAULT ACL" COMMENT; do
+    pg_restore --list tmp/full.dump | grep -qE "^[0-9]+; [0-9]+ [0-9]+ $desc " || fail "tmp/full.dump has no $desc entries"
+done
+
+# --exclude-extension-schema and --exclude-extension-members, with a test
+# extension which creates a schema of its own and a function in public.
+# Installing it needs write access to the server's share directory.
+SHAREDIR="$(psql -tAXq -c "SELECT setting FROM pg_config WHERE name = 'SHAREDIR'")"
+if [ -w "$SHAREDIR/extension" ]; then
+    cp extension/pg_split_dump_test.control extension/pg_split_dump_test--1.0.sql "$SHAREDIR/extension/"
+    dropdb --if-exists "${DBNAME}_ext"
+    createdb "${DBNAME}_ext"
+    psql -1 -X -q -v ON_ERROR_STOP=1 -d "${DBNAME}_ext" <<'SQL'
+CREATE EXTENSION pg_split_dump_test;
+CREATE TABLE ext_schema.user_tbl(a integer);
+REVOKE EXECUTE ON FUNCTION public.ext_public_fn() FROM PUBLIC;
+CREATE TABLE user_tbl(a integer);
+SQL
+    EXT_CONNINFO="user=$PGUSER host=$PGHOST dbname=${DBNAME}_ext"
+
+    # pg_dump leaves out the members, but not the other objects in the schema
+    # or the privileges changed on the members.
+    RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" "$EXT_CONNINFO" tmp/ext
+    expect_line tmp/ext/manifest.tsv "$(printf 'ext_schema/TABLES/user_tbl.sql\tTABLE\text_schema\tuser_tbl')"
+    expect_line tmp/ext/public/FUNCTIONS/ext_public_fn.sql 'REVOKE ALL ON FUNCTION public.ext_public_fn() FROM PUBLIC;'
+
+    RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" --exclude-extension-schema=pg_split_dump_test "$EXT_CONNINFO" tmp/ext_schema
+    if [ -e tmp/ext_schema/ext_schema ]; then
+        fail "the extension's schema was written despite --exclude-extension-schema"
+    fi
+    expect_line tmp/ext_schema/EXTENSIONS/pg_split_dump_test.sql 'CREATE EXTENSION IF NOT EXISTS pg_split_dump_test WITH SCHEMA public;'
+    expect_line tmp/ext_schema/manifest.tsv "$(printf 'public/TABLES/user_tbl.sql\tTABLE\tpublic\tuser_tbl')"
+    expect_line tmp/ext_schema/public/FUNCTIONS/ext_public_fn.sql 'REVOKE ALL ON FUNCTION public.ext_public_fn() FROM PUBLIC;'
+
+    RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" --exclude-extension-members "$EXT_CONNINFO" tmp/ext_members
+    if [ -e tmp/ext_members/ext_schema ] || [ -e tmp/ext_members/public/FUNCTIONS ]; then
+        fail "extension members were written despite --exclude-extension-members"
+    fi
+    expect_line tmp/ext_members/EXTENSIONS/pg_split_dump_test.sql 'CREATE EXTENSION IF NOT EXISTS pg_split_dump_test WITH SCHEMA public;'
+    expect_line tmp/ext_members/manifest.tsv "$(printf 'public/TABLES/user_tbl.sql\tTABLE\tpublic\tuser_tbl')"
+
+    # An extension which didn't create a schema, or doesn't exist, is an
+    # error before pg_dump even gets started.
+    cat > tmp/recording_pg_dump <<STUB
+#!/bin/bash
+if [ "\$1" = "--version" ]; then
+    exec "$PG_DUMP" --version
+fi
+touch "$(pwd)/tmp/pg_dump_started"
+exec "$PG_DUMP" "\$@"
+STUB
+    chmod +x tmp/recording_pg_dump
+    for extension in plpgsql no_such_extension; do
+        status=0
+        ../target/debug/pg_split_dump --pg-dump-binary=tmp/recording_pg_dump --exclude-extension-schema=$extension "$EXT_CONNINFO" tmp/ext_$extension 2> tmp/ext_$extension.log || status=$?
+        [ $status -eq 1 ] || fail "exit status $status with --exclude-extension-schema=$extension, expected 1"
+        if [ -e tmp/pg_dump_started ]; then
+            fail "pg_dump was started despite --exclude-extension-schema=$extension"
+        fi
+    done
+    grep -qF 'extension "plpgsql" did not create any schemas' tmp/ext_plpgsql.log || fail "unexpected error: $(cat tmp/ext_plpgsql.log)"
+    grep -qF 'extension "no_such_extension" does not exist' tmp/ext_no_such_extension.log || fail "unexpected error: $(cat tmp/ext_no_such_extension.log)"
+
+    dropdb "${DBNAME}_ext"
+else
+    echo "skipping the extension tests: $SHAREDIR/extension is not writable"
+fi
+
+# -W reads the password from stdin without a terminal, and hands it to
+# pg_dump in its environment, never on the command line.  The test server
+# trusts local connections, so any password does.
+cat > tmp/password_pg_dump <<STUB
+#!/bin/bash
+if [ "\$1" = "--version" ]; then
+    exec "$PG_DUMP" --version
+fi
+printf '%s\n' "\$PGPASSWORD" > "$(pwd)/tmp/pg_dump_password"
+printf '%s\n' "\$@" > "$(pwd)/tmp/pg_dump_args"
+exec "$PG_DUMP" "\$@"
+STUB
+chmod +x tmp/password_pg_dump
+echo 'typed secret' | setsid -w env -u PGPASSWORD ../target/debug/pg_split_dump --pg-dump-binary=tmp/password_pg_dump -W "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/password 2> tmp/password.log
+[ "$(cat tmp/pg_dump_password)" = "typed secret" ] || fail "pg_dump did not get the password: $(cat tmp/pg_dump_password)"
+if grep -qF "typed secret" tmp/pg_dump_args; then
+    fail "the password was passed to pg_dump on its command line"
+fi
+grep -qF "Password: " tmp/password.log || fail "no password prompt: $(cat tmp/password.log)"
+
+# A pg_dump which ignores SIGTERM (and which sleep inherits that from) gets
+# killed a while after it's been asked to exit, whether because of
+# --pg-dump-timeout or because we were interrupted.
+cat > tmp/stuck_pg_dump <<STUB
+#!/bin/bash
+if [ "\$1" = "--version" ]; then
+    exec "$PG_DUMP" --version
+fi
+trap '' TERM
+echo \$\$ > "$(pwd)/tmp/stuck_pg_dump.pid"
+exec sleep 60
+STUB
+chmod +x tmp/stuck_pg_dump
+
+# Checks that the stuck pg_dump is gone, and that it took at most the given
+# number of seconds to get rid of it.
+expect_stuck_pg_dump_killed() {
+    if kill -0 "$(cat tmp/stuck_pg_dump.pid)" 2> /dev/null; then
+        fail "pg_dump is still running after $1"
+    fi
+    [ $((SECONDS - started)) -le $2 ] || fail "getting rid of pg_dump after $1 took $((SECONDS - started)) seconds"
+}
+
+started=$SECONDS
+status=0
+../target/debug/pg_split_dump --pg-dump-binary=tmp/stuck_pg_dump --pg-dump-timeout=1 "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/stuck_timeout 2> tmp/stuck_timeout.log || status=$?
+[ $status -eq 1 ] || fail "exit status $status after pg_dump timed out, expected 1"
+grep -qF "pg_dump did not finish within 1 seconds, and was killed" tmp/stuck_timeout.log || fail "unexpected error after pg_dump timed out: $(cat tmp/stuck_timeout.log)"
+expect_stuck_pg_dump_killed "a timeout" 15
+
+rm -f tmp/stuck_pg_dump.pid
+../target/debug/pg_split_dump --pg-dump-binary=tmp/stuck_pg_dump "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/stuck_signal 2> tmp/stuck_signal.log &
+pid=$!
+for _ in $(seq 100); do
+    [ -s tmp/stuck_pg_dump.pid ] && break
+    sleep 0.1
+done
+[ -s tmp/stuck_pg_dump.pid ] || fail "the stuck pg_dump never started"
+started=$SECONDS
+kill -TERM $pid
+status=0
+wait $pid || status=$?
+[ $status -eq 143 ] || fail "exit status $status after SIGTERM, expected 143"
+grep -qF "interrupted by signal 15; pg_dump was killed" tmp/stuck_signal.log || fail "unexpected error after SIGTERM: $(cat tmp/stuck_signal.log)"
+expect_stuck_pg_dump_killed "SIGTERM" 15
+
+# Publications of whole schemas, which are new in PostgreSQL 15.  The tag
+# doesn't quote the publication's name either.
+if [ "$POSTGRES_VERSION" -ge 150000 ]; then
+    psql -X -q -v ON_ERROR_STOP=1 -d "$DBNAME" -c 'CREATE PUBLICATION "schema pub" FOR TABLES IN SCHEMA "My Schema"'
+    RUST_BACKTRACE=1 ../target/debug/pg_split_dump --pg-dump-binary="$PG_DUMP" "user=$PGUSER host=$PGHOST dbname=$DBNAME" tmp/schema_pub
+    psql -X -q -v ON_ERROR_STOP=1 -d "$DBNAME" -c 'DROP PUBLICATION "schema pub"'
+
+    expect_line tmp/schema_pub/manifest.tsv "$(printf 'PUBLICATIONS/schema pub/schema pub.sql\tPUBLICATION TABLES IN SCHEMA\tMy Schema\tschema pub My Schema')"
+    expect_line "tmp/schema_pub/PUBLICATIONS/schema pub/schema pub.sql" 'ALTER PUBLICATION "schema pub" ADD TABLES IN SCHEMA "My Schema";'
+fi
@@ -0,0 +1,119 @@
+#!/bin/bash
+
+# Runs pg_split_dump against a throwaway PostgreSQL cluster with a server
+# certificate signed by a self-signed CA, checking that each sslmode accepts
+# or rejects the connection the way libpq would.  Needs initdb, pg_ctl and
+# the openssl command line tool, and must not be run as root.
+
+set -eux
+
+PG_DUMP="$(which pg_dump)"
+PG_BINDIR="${PG_BINDIR:-$(pg_config --bindir 2>/dev/null || dirname "$(which initdb)")}"
+PORT=${PGPORT_TLS:-54329}
+PG_SPLIT_DUMP="$(pwd)/../target/debug/pg_split_dump"
+
+WORKDIR="$(mktemp -d)"
+cleanup() {
+    "$PG_BINDIR/pg_ctl" -D "$WORKDIR/data" -m immediate stop >/dev/null 2>&1 || true
+    rm -rf "$WORKDIR"
+}
+trap cleanup EXIT
+
+# Keep libpq and pg_split_dump away from the user's own ~/.postgresql.
+export HOME="$WORKDIR/home"
+mkdir -p "$HOME"
+unset PGSSLMODE PGSSLROOTCERT PGSSLCERT PGSSLKEY PGSSLCRL PGSSLCRLDIR PGHOST PGHOSTADDR PGPORT
+
+fail() {
+    set +x
+    echo "pg_split_dump TLS tests FAILED: $1" >&1
+    exit 1
+}
+
+# Certificates
+cd "$WORKDIR"
+openssl req -x509 -newkey rsa:2048 -nodes -days 1 -subj "/CN=pg_split_dump test CA" \
+    -keyout ca.key -out ca.crt
+openssl req -x509 -newkey rsa:2048 -nodes -days 1 -subj "/CN=another CA" \
+    -keyout other_ca.key -out other_ca.crt
+
+openssl req -newkey rsa:2048 -nodes -subj "/CN=localhost" -keyout server.key -out server.csr
+printf 'subjectAltName=DNS:localhost\n' > server.ext
+openssl x509 -req -in server.csr -CA ca.crt -CAkey ca.key -CAcreateserial -days 1 \
+    -extfile server.ext -out server.crt
+
+openssl req -newkey rsa:2048 -nodes -subj "/CN=certuser" -keyout client.key -out client.csr
+openssl x509 -req -in client.csr -CA ca.crt -CAkey ca.key -CAcreateserial -days 1 -out client.crt
+openssl pkcs8 -topk8 -nocrypt -in client.key -out client_pkcs8.key
+openssl pkey -in client.key -traditional -out client_pkcs1.key
+grep -q 'BEGIN RSA PRIVATE KEY' client_pkcs1.key || fail "could not create a PKCS #1 key"
+chmod 0600 ./*.key
+
+# Cluster
+"$PG_BINDIR/initdb" -D data -U postgres --auth=trust >/dev/null
+cp server.crt server.key ca.crt data/
+cat >> data/postgresql.conf <<CONF
+port = $PORT
+listen_addresses = 'localhost'
+unix_socket_directories = '$WORKDIR'
+ssl = on
+ssl_cert_file = 'server.crt'
+ssl_key_file = 'server.key'
+ssl_ca_file = 'ca.crt'
+CONF
+cat > data/pg_hba.conf <<HBA
+local     all  all                    trust
+hostssl   all  postgres  127.0.0.1/32 trust
+hostssl   all  postgres  ::1/128      trust
+hostssl   all  certuser  127.0.0.1/32 cert
+hostssl   all  certuser  ::1/128      cert
+hostnossl all  all       0.0.0.0/0    reject
+hostnossl all  all       ::/0         reject
+HBA
+"$PG_BINDIR/pg_ctl" -D data -l server.log -w start
+"$PG_BINDIR/psql" -X -q -h "$WORKDIR" -p "$PORT" -U postgres -d postgres \
+    -c "CREATE ROLE certuser LOGIN; CREATE TABLE tls_test(a int); GRANT SELECT ON tls_test TO certuser;"
+
+n=0
+# Runs pg_split_dump with the given connection options appended.
+dump() {
+    n=$((n + 1))
+    "$PG_SPLIT_DUMP" --pg-dump-binary="$PG_DUMP" "port=$PORT dbname=postgres $1" "out$n" >"out$n.log" 2>&1
+}
+expect_success() {
+    dump "$1" || fail "could not connect with \"$1\": $(cat "out$n.log")"
+    [ -f "out$n/public/TABLES/tls_test.sql" ] || fail "nothing dumped with \"$1\""
+}
+expect_failure() {
+    if dump "$1"; then
+        fail "connected with \"$1\""
+    fi
+    grep -qF -- "$2" "out$n.log" || fail "unexpected error with \"$1\": $(cat "out$n.log")"
+}
+
+expect_failure "host=localhost user=postgres sslmode=disable" "no encryption"
+expect_success "host=localhost user=postgres sslmode=require"
+expect_success "host=localhost user=postgres sslmode=verify-ca sslrootcert=$WORKDIR/ca.crt"
+expect_success "host=localhost user=postgres sslmode=verify-full sslrootcert=$WORKDIR/ca.crt"
+expect_success "host=127.0.0.1 user=postgres sslmode=verify-ca sslrootcert=$WORKDIR/ca.crt"
+expect_failure "host=127.0.0.1 user=postgres sslmode=verify-full sslrootcert=$WORKDIR/ca.crt" "certificate"
+expect_failure "host=localhost user=postgres sslmode=verify-ca sslrootcert=$WORKDIR/other_ca.crt" "certificate"
+expect_failure "host=localhost user=postgres sslmode=verify-ca" "root certificate file"
+expect_failure "host=localhost user=postgres sslmode=require sslrootcert=$WORKDIR/ca.crt sslcrl=$WORKDIR/none.crl" "sslcrl is not supported"
+expect_failure "host=localhost user=postgres sslmode=require sslcrldir=$WORKDIR" "sslcrldir is not supported"
+
+# The default root certificate, and a revocation list next to it.
+mkdir -p "$HOME/.postgresql"
+cp ca.crt "$HOME/.postgresql/root.crt"
+expect_success "host=localhost user=postgres sslmode=verify-full"
+touch "$HOME/.postgresql/root.crl"
+expect_failure "host=localhost user=postgres sslmode=verify-full" "root.crl"
+rm "$HOME/.postgresql/root.crl"
+
+# Client certificates, with the key in either format.
+expect_success "host=localhost user=certuser sslmode=verify-full sslcert=$WORKDIR/client.crt sslkey=$WORKDIR/client_pkcs8.key"
+expect_success "host=localhost user=certuser sslmode=verify-full sslcert=$WORKDIR/client.crt sslkey=$WORKDIR/client_pkcs1.key"
+expect_failure "host=localhost user=certuser sslmode=verify-full" "certificate"
+
+set +x
+echo "pg_split_dump TLS tests passed"